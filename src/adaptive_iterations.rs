@@ -0,0 +1,73 @@
+use crate::iteration_image::IterationImage;
+
+/// Limit for the home view, which the zoom depth estimate grows from.
+const BASE_ITERATIONS: f64 = 256.0;
+
+/// Horizontal radius of the home view, where zoom depth is 0.
+const HOME_RADIUS: f64 = 2.0;
+
+/// Power of the zoom depth, in decades plus one, the estimate grows with.
+const DEPTH_EXPONENT: f64 = 1.5;
+
+const MIN_ITERATIONS: u32 = 64;
+const MAX_ITERATIONS: u32 = 1 << 20;
+
+/// Range the image based correction of the estimate is kept within, in doublings.
+const MIN_DOUBLINGS: i32 = -2;
+const MAX_DOUBLINGS: i32 = 8;
+
+/// Fraction of samples escaping in the upper half of the limit above which it is too low, since
+/// the boundary is still being resolved there.
+const RAISE_FRACTION: f64 = 0.01;
+
+/// Fraction of samples escaping in the upper half of the limit below which it is too high, since
+/// halving it would hardly change the image.
+const LOWER_FRACTION: f64 = 0.001;
+
+/// Iteration limit chosen automatically from how deep the view is zoomed, corrected by doubling
+/// or halving it while the last image shows it is too low or needlessly high.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AdaptiveIterations {
+    /// Doublings of the zoom depth estimate asked for by earlier images.
+    doublings: i32,
+}
+
+impl AdaptiveIterations {
+    /// Limit for a view with horizontal radius `radius`.
+    pub fn max_iterations(&self, radius: f64) -> u32 {
+        let decades = (HOME_RADIUS / radius).log10().max(0.0);
+        let estimate = BASE_ITERATIONS * (1.0 + decades).powf(DEPTH_EXPONENT);
+        let iterations = estimate * 2.0_f64.powi(self.doublings);
+        // The cast saturates for views too deep for any sensible estimate
+        (iterations as u32).clamp(MIN_ITERATIONS, MAX_ITERATIONS)
+    }
+
+    /// Adjusts the correction from an image rendered with the limit this gave. Samples hitting the
+    /// limit alone say little, as every interior point does, so only while some do are samples
+    /// escaping close below it taken as a sign the limit cuts detail off. The limit is only halved
+    /// if the halved one would not ask to be raised again straight away.
+    pub fn update(&mut self, image: &IterationImage) {
+        let max_iterations = image.max_iterations();
+        let samples = image.iterations();
+        let count_escaping = |above: u32, up_to: u32| {
+            samples
+                .iter()
+                .filter(|&&iterations| iterations > above && iterations <= up_to)
+                .count() as f64
+                / samples.len() as f64
+        };
+        let hitting_limit = samples
+            .iter()
+            .any(|&iterations| iterations >= max_iterations);
+        // Escapes at the limit itself count as hitting it
+        let late_fraction = count_escaping(max_iterations / 2, max_iterations.saturating_sub(1));
+        if hitting_limit && late_fraction > RAISE_FRACTION {
+            self.doublings = (self.doublings + 1).min(MAX_DOUBLINGS);
+        } else if late_fraction < LOWER_FRACTION
+            && count_escaping(max_iterations / 4, (max_iterations / 2).saturating_sub(1))
+                <= RAISE_FRACTION
+        {
+            self.doublings = (self.doublings - 1).max(MIN_DOUBLINGS);
+        }
+    }
+}
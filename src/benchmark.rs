@@ -0,0 +1,288 @@
+use std::{
+    fmt::Write as _,
+    fs, io,
+    num::NonZeroUsize,
+    path::Path,
+    process::Command,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use num::Complex;
+
+use crate::{
+    render::{
+        cpu::ScalarCpuRenderer,
+        opencl::{all_devices, OpenclRenderer},
+        RenderOptions, Renderer,
+    },
+    Dimensions,
+};
+
+/// Where `--bench` writes its report unless `--bench-report` says otherwise.
+pub const DEFAULT_REPORT_PATH: &str = "bench.json";
+
+/// Timed renders of every case, after one untimed warm-up render.
+const REPETITIONS: usize = 5;
+
+struct BenchCase {
+    name: &'static str,
+    center: Complex<f64>,
+    radius: f64,
+    max_iterations: u32,
+    width: usize,
+    height: usize,
+}
+
+impl BenchCase {
+    fn dimensions(&self) -> Dimensions {
+        Dimensions {
+            width: NonZeroUsize::new(self.width).unwrap(),
+            height: NonZeroUsize::new(self.height).unwrap(),
+        }
+    }
+}
+
+/// Mostly escaping views, mostly interior ones and a deep zoom, at a few sizes.
+fn cases() -> [BenchCase; 5] {
+    [
+        BenchCase {
+            name: "full_set",
+            center: Complex::new(-0.75, 0.0),
+            radius: 2.0,
+            max_iterations: 256,
+            width: 640,
+            height: 480,
+        },
+        BenchCase {
+            name: "full_set_1080p",
+            center: Complex::new(-0.75, 0.0),
+            radius: 2.0,
+            max_iterations: 256,
+            width: 1920,
+            height: 1080,
+        },
+        BenchCase {
+            name: "seahorse_valley",
+            center: Complex::new(-0.745, 0.1),
+            radius: 0.01,
+            max_iterations: 1024,
+            width: 640,
+            height: 480,
+        },
+        BenchCase {
+            name: "cardioid_interior",
+            center: Complex::new(-0.1, 0.0),
+            radius: 0.3,
+            max_iterations: 4096,
+            width: 320,
+            height: 240,
+        },
+        BenchCase {
+            name: "deep_spiral",
+            center: Complex::new(-0.743643887037151, 0.13182590420533),
+            radius: 1e-9,
+            max_iterations: 8192,
+            width: 320,
+            height: 240,
+        },
+    ]
+}
+
+struct CaseResult {
+    backend: String,
+    case: &'static str,
+    width: usize,
+    height: usize,
+    max_iterations: u32,
+    min: Duration,
+    median: Duration,
+    max: Duration,
+    /// Iterations summed over every pixel of one render.
+    pixel_iterations: u64,
+}
+
+impl CaseResult {
+    fn mpixel_iterations_per_second(&self) -> f64 {
+        self.pixel_iterations as f64 / self.median.as_secs_f64() / 1e6
+    }
+}
+
+/// Renders every case `REPETITIONS` times, resizing the renderer for each with `resize`.
+fn run_cases<R: Renderer<f64>, E: From<R::Error>>(
+    backend: &str,
+    renderer: &mut R,
+    resize: impl Fn(&mut R, Dimensions) -> Result<(), E>,
+) -> Result<Vec<CaseResult>, E> {
+    let options = RenderOptions::default();
+    let mut results = Vec::new();
+    for case in cases() {
+        resize(renderer, case.dimensions())?;
+        let image = renderer.render(case.center, case.radius, case.max_iterations, &options)?;
+        let pixel_iterations = image.iterations().iter().map(|&n| u64::from(n)).sum();
+        let mut durations = (0..REPETITIONS)
+            .map(|_| {
+                let instant = Instant::now();
+                renderer.render(case.center, case.radius, case.max_iterations, &options)?;
+                Ok(instant.elapsed())
+            })
+            .collect::<Result<Vec<_>, E>>()?;
+        durations.sort();
+        let result = CaseResult {
+            backend: backend.to_owned(),
+            case: case.name,
+            width: case.width,
+            height: case.height,
+            max_iterations: case.max_iterations,
+            min: durations[0],
+            median: durations[REPETITIONS / 2],
+            max: durations[REPETITIONS - 1],
+            pixel_iterations,
+        };
+        print_result(&result);
+        results.push(result);
+    }
+    Ok(results)
+}
+
+fn print_result(result: &CaseResult) {
+    println!(
+        "{:<32} {:<18} {:>9} {:>6} {:>10.2} {:>10.2} {:>10.2} {:>12.1}",
+        result.backend,
+        result.case,
+        format!("{}x{}", result.width, result.height),
+        result.max_iterations,
+        result.min.as_secs_f64() * 1e3,
+        result.median.as_secs_f64() * 1e3,
+        result.max.as_secs_f64() * 1e3,
+        result.mpixel_iterations_per_second()
+    );
+}
+
+/// Hash of the checked out commit, if the program still runs from its source tree.
+fn commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            character if character.is_control() => {
+                write!(escaped, "\\u{:04x}", u32::from(character)).unwrap()
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// `value` to three decimals, or `null` if it is infinite or NaN, which JSON has no numbers for.
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value:.3}")
+    } else {
+        "null".to_owned()
+    }
+}
+
+fn report(results: &[CaseResult]) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let commit = commit().map_or_else(|| "null".to_owned(), |commit| json_string(&commit));
+    let mut report = String::new();
+    // Writing to a string cannot fail
+    writeln!(report, "{{").unwrap();
+    writeln!(
+        report,
+        "  \"version\": {},",
+        json_string(env!("CARGO_PKG_VERSION"))
+    )
+    .unwrap();
+    writeln!(report, "  \"commit\": {commit},").unwrap();
+    writeln!(report, "  \"timestamp\": {timestamp},").unwrap();
+    writeln!(report, "  \"repetitions\": {REPETITIONS},").unwrap();
+    writeln!(report, "  \"results\": [").unwrap();
+    for (index, result) in results.iter().enumerate() {
+        let separator = if index + 1 < results.len() { "," } else { "" };
+        writeln!(
+            report,
+            "    {{\"backend\": {}, \"case\": {}, \"width\": {}, \"height\": {}, \
+             \"max_iterations\": {}, \"min_ms\": {}, \"median_ms\": {}, \"max_ms\": {}, \
+             \"mpixel_iterations_per_second\": {}}}{separator}",
+            json_string(&result.backend),
+            json_string(result.case),
+            result.width,
+            result.height,
+            result.max_iterations,
+            json_number(result.min.as_secs_f64() * 1e3),
+            json_number(result.median.as_secs_f64() * 1e3),
+            json_number(result.max.as_secs_f64() * 1e3),
+            json_number(result.mpixel_iterations_per_second())
+        )
+        .unwrap();
+    }
+    writeln!(report, "  ]").unwrap();
+    writeln!(report, "}}").unwrap();
+    report
+}
+
+/// Runs every case on the CPU and on every OpenCL device, printing results as they come in, and
+/// writes a JSON report of all of them to `report_path`. Devices that fail are skipped.
+pub fn run(report_path: &Path) -> io::Result<()> {
+    println!(
+        "{:<32} {:<18} {:>9} {:>6} {:>10} {:>10} {:>10} {:>12}",
+        "backend", "case", "size", "iters", "min ms", "median ms", "max ms", "Mpix-it/s"
+    );
+    let mut results = Vec::new();
+
+    let first = cases()[0].dimensions();
+    // The case sizes are far from overflowing
+    let mut cpu_renderer = ScalarCpuRenderer::new(first.width, first.height).unwrap();
+    let threads = rayon::current_num_threads();
+    let backend = format!(
+        "CPU scalar ({threads} thread{})",
+        if threads == 1 { "" } else { "s" }
+    );
+    let Ok(cpu_results) = run_cases::<_, !>(&backend, &mut cpu_renderer, |renderer, dimensions| {
+        renderer.resize(dimensions).unwrap();
+        Ok(())
+    });
+    results.extend(cpu_results);
+
+    match all_devices() {
+        Ok(devices) => {
+            for device in devices {
+                let name = device
+                    .name()
+                    .unwrap_or_else(|_| "unknown device".to_owned());
+                let backend = format!("OpenCL: {name}");
+                let device_results =
+                    OpenclRenderer::with_device(&device, first.width, first.height).and_then(
+                        |mut renderer| run_cases(&backend, &mut renderer, OpenclRenderer::resize),
+                    );
+                match device_results {
+                    Ok(device_results) => results.extend(device_results),
+                    Err(error) => eprintln!("skipping {backend}: {error:?}"),
+                }
+            }
+        }
+        Err(error) => eprintln!("skipping OpenCL: {error:?}"),
+    }
+
+    fs::write(report_path, report(&results))?;
+    println!("report written to {}", report_path.display());
+    Ok(())
+}
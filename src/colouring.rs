@@ -0,0 +1,231 @@
+use rayon::slice::ParallelSliceMut;
+
+use crate::{iteration_image::IterationImage, palette::Palette, render::orbit_trap::TrapHit};
+
+/// Distance from the set, in pixels, at which distance estimate shading reaches about 63% white.
+pub const DISTANCE_FALLOFF: f64 = 4.0;
+
+/// Distance from an orbit trap at which orbit trap shading falls to about 37% brightness.
+pub const TRAP_FALLOFF: f64 = 0.25;
+
+/// Fraction of the palette orbit trap shading advances by for every iteration later an orbit
+/// came closest to the trap, so orbits trapped at different iterations stand apart.
+pub const TRAP_ITERATION_STEP: f64 = 0.05;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Shading {
+    #[default]
+    EscapeTime,
+    HistogramEqualised,
+    DistanceEstimate,
+    Lighting,
+    OrbitTrap,
+}
+
+impl Shading {
+    pub fn next(self) -> Self {
+        match self {
+            Self::EscapeTime => Self::HistogramEqualised,
+            Self::HistogramEqualised => Self::DistanceEstimate,
+            Self::DistanceEstimate => Self::Lighting,
+            Self::Lighting => Self::OrbitTrap,
+            Self::OrbitTrap => Self::EscapeTime,
+        }
+    }
+}
+
+impl std::fmt::Display for Shading {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EscapeTime => write!(f, "Escape time"),
+            Self::HistogramEqualised => write!(f, "Histogram equalised"),
+            Self::DistanceEstimate => write!(f, "Distance estimate"),
+            Self::Lighting => write!(f, "Lighting"),
+            Self::OrbitTrap => write!(f, "Orbit trap"),
+        }
+    }
+}
+
+/// Everything known about a single sample of an `IterationImage`.
+#[derive(Clone, Copy, Debug)]
+pub struct Sample {
+    pub iterations: u32,
+    pub max_iterations: u32,
+    /// Exterior distance estimate in pixels, zero inside the set.
+    pub distance: Option<f32>,
+    /// Unit vector pointing away from the set, zero inside it.
+    pub normal: Option<[f32; 2]>,
+    pub trap: Option<TrapHit>,
+}
+
+/// Directional light used by `Shading::Lighting`, angles in radians.
+#[derive(Clone, Copy, Debug)]
+pub struct Light {
+    pub azimuth: f64,
+    pub elevation: f64,
+    /// How far the surface normals tilt out of the image plane; higher values flatten the relief.
+    pub height: f64,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            azimuth: std::f64::consts::FRAC_PI_4,
+            elevation: std::f64::consts::FRAC_PI_4,
+            height: 1.5,
+        }
+    }
+}
+
+impl std::fmt::Display for Light {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "azimuth {:.0}°, elevation {:.0}°, height {:.2}",
+            self.azimuth.to_degrees().rem_euclid(360.0),
+            self.elevation.to_degrees(),
+            self.height
+        )
+    }
+}
+
+impl Light {
+    /// Azimuth, elevation and height as `brightness` in `kernel.cl` expects them.
+    pub fn kernel_argument(&self) -> [f64; 4] {
+        [self.azimuth, self.elevation, self.height, 0.0]
+    }
+
+    /// Lambertian reflectance of a surface whose normal projects onto `normal` in the plane.
+    fn brightness(&self, [normal_x, normal_y]: [f32; 2]) -> f64 {
+        let normal = [f64::from(normal_x), f64::from(normal_y), self.height];
+        let length = normal.iter().map(|x| x * x).sum::<f64>().sqrt();
+        let light = [
+            self.elevation.cos() * self.azimuth.cos(),
+            self.elevation.cos() * self.azimuth.sin(),
+            self.elevation.sin(),
+        ];
+        (normal.iter().zip(light).map(|(n, l)| n * l).sum::<f64>() / length).max(0.0)
+    }
+}
+
+/// Cumulative distribution of the iteration counts of escaped samples.
+#[derive(Clone, Debug)]
+struct Histogram {
+    max_iterations: u32,
+    /// Distinct iteration counts in ascending order.
+    iterations: Vec<u32>,
+    /// Fraction of escaped samples with at most the corresponding iteration count.
+    cumulative: Vec<f64>,
+}
+
+impl Histogram {
+    fn new(image: &IterationImage) -> Self {
+        let max_iterations = image.max_iterations();
+        let mut escaped = image
+            .iterations()
+            .iter()
+            .copied()
+            .filter(|&iterations| iterations < max_iterations)
+            .collect::<Vec<_>>();
+        escaped.par_sort_unstable();
+        let total = escaped.len() as f64;
+        let mut iterations = Vec::new();
+        let mut cumulative = Vec::new();
+        for (index, &count) in escaped.iter().enumerate() {
+            if escaped.get(index + 1) != Some(&count) {
+                iterations.push(count);
+                cumulative.push((index + 1) as f64 / total);
+            }
+        }
+        Self {
+            max_iterations,
+            iterations,
+            cumulative,
+        }
+    }
+
+    fn equalise(&self, iterations: u32) -> f64 {
+        if iterations >= self.max_iterations {
+            return 1.0;
+        }
+        // Counts missing from the histogram take the value of the next smaller one present
+        match self
+            .iterations
+            .partition_point(|&count| count <= iterations)
+        {
+            0 => 0.0,
+            index => self.cumulative[index - 1],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Colouring {
+    pub shading: Shading,
+    pub light: Light,
+    pub palette: Palette,
+    /// Rotation of the palette for escaped samples, as a fraction of its length.
+    pub cycle_offset: f64,
+    histogram: Option<Histogram>,
+    /// Keeps the current histogram across frames instead of rebuilding it from every image.
+    pub histogram_locked: bool,
+}
+
+impl Colouring {
+    /// Updates whatever the colouring derives from the image about to be coloured, which may
+    /// be the same one as last time.
+    pub fn prepare(&mut self, image: &IterationImage, image_is_new: bool) {
+        if self.shading != Shading::HistogramEqualised {
+            return;
+        }
+        let stale = self
+            .histogram
+            .as_ref()
+            .is_none_or(|histogram| histogram.max_iterations != image.max_iterations());
+        if stale || (image_is_new && !self.histogram_locked) {
+            self.histogram = Some(Histogram::new(image));
+        }
+    }
+
+    pub fn colour(&self, sample: Sample) -> [u8; 3] {
+        let escape_time = f64::from(sample.iterations) / f64::from(sample.max_iterations);
+        let t = match self.shading {
+            Shading::EscapeTime => escape_time,
+            Shading::HistogramEqualised => match &self.histogram {
+                Some(histogram) => histogram.equalise(sample.iterations),
+                None => escape_time,
+            },
+            Shading::DistanceEstimate => match sample.distance {
+                // Thin filaments stay dark even where they are far narrower than a pixel
+                Some(distance) => 1.0 - (-f64::from(distance) / DISTANCE_FALLOFF).exp(),
+                None => escape_time,
+            },
+            Shading::Lighting => match sample.normal {
+                Some(normal) if sample.iterations < sample.max_iterations => {
+                    self.light.brightness(normal)
+                }
+                Some(_) => 0.0,
+                None => escape_time,
+            },
+            Shading::OrbitTrap => match sample.trap {
+                Some(trap) => (f64::from(trap.iteration) * TRAP_ITERATION_STEP).fract(),
+                None => escape_time,
+            },
+        };
+        // The set itself keeps its colour while the palette cycles
+        let cycle_offset = if sample.iterations < sample.max_iterations {
+            self.cycle_offset
+        } else {
+            0.0
+        };
+        let colour = self.palette.colour(t, cycle_offset);
+        match (self.shading, sample.trap) {
+            // Orbits passing close to the trap are bright, the others fade to black
+            (Shading::OrbitTrap, Some(trap)) => {
+                let closeness = (-f64::from(trap.distance) / TRAP_FALLOFF).exp();
+                colour.map(|channel| (f64::from(channel) * closeness) as u8)
+            }
+            _ => colour,
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{iteration_image::IterationImage, rgb_image::RgbImage};
+
+/// What the backend comparison shows in place of the usual image.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ComparisonView {
+    Off,
+    /// The CPU image on the left half and the OpenCL one on the right.
+    Split,
+    Heatmap,
+}
+
+impl ComparisonView {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Split,
+            Self::Split => Self::Heatmap,
+            Self::Heatmap => Self::Off,
+        }
+    }
+}
+
+/// Per-pixel iteration differences between two renders of the same view.
+pub struct Difference {
+    /// Largest difference between corresponding samples of every pixel.
+    differences: Vec<u32>,
+    width: usize,
+    max_iterations: u32,
+}
+
+impl Difference {
+    /// Compares two images rendered with the same view and options. Images of different sizes
+    /// count every pixel of the larger one as mismatched.
+    pub fn new(a: &IterationImage, b: &IterationImage) -> Self {
+        let samples_per_pixel = a.samples_per_pixel().get();
+        let pixel_count = a.iterations().len().max(b.iterations().len()) / samples_per_pixel;
+        let differences = if a.iterations().len() == b.iterations().len()
+            && a.samples_per_pixel() == b.samples_per_pixel()
+        {
+            a.iterations()
+                .chunks(samples_per_pixel)
+                .zip(b.iterations().chunks(samples_per_pixel))
+                .map(|(a, b)| {
+                    a.iter()
+                        .zip(b)
+                        .map(|(a, b)| a.abs_diff(*b))
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect()
+        } else {
+            vec![a.max_iterations(); pixel_count]
+        };
+        Self {
+            differences,
+            width: a.width().get(),
+            max_iterations: a.max_iterations(),
+        }
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        self.differences.len()
+    }
+
+    pub fn mismatched_pixels(&self) -> usize {
+        self.differences
+            .iter()
+            .filter(|&&difference| difference != 0)
+            .count()
+    }
+
+    pub fn max_difference(&self) -> u32 {
+        self.differences.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Matching pixels in black and mismatched ones from dark red for a single iteration to white
+    /// for `max_iterations`, on a logarithmic scale so isolated off-by-one pixels stand out.
+    pub fn heatmap(&self) -> RgbImage {
+        let scale = f64::from(self.max_iterations.max(1)).ln_1p();
+        let buffer = self
+            .differences
+            .iter()
+            .flat_map(|&difference| {
+                if difference == 0 {
+                    return [0, 0, 0];
+                }
+                let t = 0.25 + 0.75 * f64::from(difference).ln_1p() / scale;
+                let channel = |start: f64| ((t - start) / (1.0 - start)).clamp(0.0, 1.0);
+                [channel(0.0), channel(0.5), channel(0.75)]
+                    .map(|channel| (channel * 255.0).round() as u8)
+            })
+            .collect();
+        // Every pixel contributed three bytes
+        RgbImage::from_buffer(buffer, self.width.try_into().unwrap()).unwrap()
+    }
+}
+
+impl std::fmt::Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} pixels differ",
+            self.mismatched_pixels(),
+            self.pixel_count()
+        )?;
+        if self.max_difference() > 0 {
+            write!(f, ", by up to {} iterations", self.max_difference())?;
+        }
+        Ok(())
+    }
+}
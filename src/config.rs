@@ -0,0 +1,298 @@
+use std::{
+    env, fs, io,
+    num::{NonZeroU16, NonZeroU32, NonZeroUsize},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use sdl2::keyboard::Scancode;
+
+use crate::{
+    error::ConfigError,
+    keymap::{Action, Keymap},
+    palette::lines,
+    Dimensions, RendererChoice,
+};
+
+/// Everything the configuration file can set, with the defaults for what it leaves out.
+pub struct Config {
+    pub window_dimensions: Dimensions,
+    /// Size of the HUD's text in points, or `None` to scale it with the window.
+    pub font_size: Option<NonZeroU16>,
+    pub backend: RendererChoice,
+    pub max_iterations: u32,
+    pub adaptive_iterations: bool,
+    /// Palette loaded at startup unless `--palette` names another. Relative paths are relative to
+    /// the directory of the configuration file.
+    pub palette: Option<PathBuf>,
+    pub keymap: Keymap,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            window_dimensions: Dimensions {
+                width: NonZeroUsize::new(1280).unwrap(),
+                height: NonZeroUsize::new(720).unwrap(),
+            },
+            font_size: None,
+            backend: RendererChoice::Cpu,
+            max_iterations: 256,
+            adaptive_iterations: false,
+            palette: None,
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    Window,
+    Render,
+    Keys,
+}
+
+impl Config {
+    /// `config.ini` in the `fraktaloj_gui` directory of the XDG configuration directory, or `None`
+    /// if neither `XDG_CONFIG_HOME` nor `HOME` says where that is.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            // Relative paths are to be ignored according to the XDG specification
+            .filter(|path| path.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_home.join("fraktaloj_gui").join("config.ini"))
+    }
+
+    /// Reads the configuration file at `path`. A missing file gives the defaults unless
+    /// `required` is set.
+    pub fn load(path: &Path, required: bool) -> Result<Self, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(source) => {
+                let mut config = Self::parse(&source)?;
+                if let (Some(palette), Some(directory)) = (&mut config.palette, path.parent()) {
+                    // Joining leaves absolute paths as they are
+                    *palette = directory.join(&*palette);
+                }
+                Ok(config)
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound && !required => {
+                Ok(Self::default())
+            }
+            Err(error) => Err(ConfigError::Io(error)),
+        }
+    }
+
+    /// Parses `key = value` lines grouped under `[window]`, `[render]` and `[keys]` headers, with
+    /// `#` starting comment lines. Keys are bound by their SDL names, like `Page Up` or `.`, or
+    /// unbound with `none`.
+    pub fn parse(source: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        let mut section = None;
+        for (line, text) in lines(source) {
+            if let Some(name) = text
+                .strip_prefix('[')
+                .and_then(|text| text.strip_suffix(']'))
+            {
+                section = Some(match name.trim() {
+                    "window" => Section::Window,
+                    "render" => Section::Render,
+                    "keys" => Section::Keys,
+                    name => return Err(syntax_error(line, format!("unknown section [{name}]"))),
+                });
+                continue;
+            }
+            let (key, value) = text
+                .split_once('=')
+                .ok_or_else(|| syntax_error(line, "expected `key = value`".to_string()))?;
+            let (key, value) = (key.trim(), value.trim());
+            let Some(section) = section else {
+                return Err(syntax_error(
+                    line,
+                    format!("{key} must follow a [window], [render] or [keys] header"),
+                ));
+            };
+            match (section, key) {
+                (Section::Window, "width") => {
+                    config.window_dimensions.width =
+                        parse_value(line, value, "a positive width in pixels")?
+                }
+                (Section::Window, "height") => {
+                    config.window_dimensions.height =
+                        parse_value(line, value, "a positive height in pixels")?
+                }
+                (Section::Window, "font_size") => {
+                    config.font_size = match value {
+                        "auto" => None,
+                        _ => Some(parse_value(
+                            line,
+                            value,
+                            "a positive font size in points or `auto`",
+                        )?),
+                    }
+                }
+                (Section::Render, "backend") => {
+                    config.backend = match value {
+                        "cpu" => RendererChoice::Cpu,
+                        "opencl" => RendererChoice::Opencl,
+                        _ => {
+                            return Err(syntax_error(
+                                line,
+                                format!("expected `cpu` or `opencl`, found `{value}`"),
+                            ))
+                        }
+                    }
+                }
+                (Section::Render, "max_iterations") => {
+                    config.max_iterations =
+                        parse_value::<NonZeroU32>(line, value, "a positive iteration limit")?.get()
+                }
+                (Section::Render, "adaptive_iterations") => {
+                    config.adaptive_iterations = parse_value(line, value, "`true` or `false`")?
+                }
+                (Section::Render, "palette") => config.palette = Some(PathBuf::from(value)),
+                (Section::Keys, action) => {
+                    let action = Action::from_name(action).ok_or_else(|| {
+                        syntax_error(line, format!("there is no action called `{action}`"))
+                    })?;
+                    let key = match value {
+                        "none" => None,
+                        _ => Some(Scancode::from_name(value).ok_or_else(|| {
+                            syntax_error(line, format!("there is no key called `{value}`"))
+                        })?),
+                    };
+                    config.keymap.bind(action, key);
+                }
+                (Section::Window | Section::Render, key) => {
+                    return Err(syntax_error(line, format!("unknown setting `{key}`")))
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+fn syntax_error(line: usize, message: String) -> ConfigError {
+    ConfigError::Syntax { line, message }
+}
+
+fn parse_value<T: FromStr>(line: usize, value: &str, what: &str) -> Result<T, ConfigError> {
+    value
+        .parse()
+        .map_err(|_| syntax_error(line, format!("expected {what}, found `{value}`")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    fn syntax_error_line(source: &str) -> usize {
+        match Config::parse(source) {
+            Err(ConfigError::Syntax { line, .. }) => line,
+            Err(error) => panic!("expected a syntax error, found {error}"),
+            Ok(_) => panic!("expected a syntax error in {source:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_every_setting() {
+        let config = Config::parse(
+            "# Comments and blank lines are skipped\n\
+             \n\
+             [window]\n\
+             width = 800\n\
+             height = 600\n\
+             font_size = 14\n\
+             [render]\n\
+             backend = opencl\n\
+             max_iterations = 1000\n\
+             adaptive_iterations = true\n\
+             palette = fire.map\n",
+        )
+        .unwrap();
+        assert_eq!(config.window_dimensions.width.get(), 800);
+        assert_eq!(config.window_dimensions.height.get(), 600);
+        assert_eq!(config.font_size, NonZeroU16::new(14));
+        assert!(matches!(config.backend, RendererChoice::Opencl));
+        assert_eq!(config.max_iterations, 1000);
+        assert!(config.adaptive_iterations);
+        assert_eq!(config.palette, Some(PathBuf::from("fire.map")));
+    }
+
+    #[test]
+    fn none_unbinds_a_key() {
+        let config = Config::parse("[keys]\nfind_minibrot = none").unwrap();
+        assert!(!config
+            .keymap
+            .actions(Action::FindMinibrot.default_key())
+            .any(|action| action == Action::FindMinibrot));
+    }
+
+    #[test]
+    fn rejects_unknown_sections_and_keys() {
+        assert_eq!(syntax_error_line("[colours]"), 1);
+        assert_eq!(syntax_error_line("[window]\ndepth = 3"), 2);
+        assert_eq!(syntax_error_line("[keys]\nfly_sideways = A"), 2);
+        assert_eq!(syntax_error_line("width = 800"), 1);
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert_eq!(syntax_error_line("[render]\nbackend = gpu"), 2);
+        assert_eq!(syntax_error_line("[render]\nmax_iterations = 0"), 2);
+        assert_eq!(syntax_error_line("[render]\nadaptive_iterations = yes"), 2);
+        assert_eq!(syntax_error_line("[window]\nwidth = -1"), 2);
+        assert_eq!(syntax_error_line("[render]\nmax_iterations"), 2);
+    }
+
+    #[test]
+    fn rejects_a_zero_font_size() {
+        assert_eq!(syntax_error_line("[window]\nfont_size = 0"), 2);
+        assert_eq!(
+            Config::parse("[window]\nfont_size = auto")
+                .unwrap()
+                .font_size,
+            None
+        );
+    }
+
+    #[test]
+    fn errors_give_the_line_counting_comments_and_blank_lines() {
+        let error = Config::parse("# Settings\n\n[window]\n\nwidth = wide")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 5: expected a positive width in pixels, found `wide`"
+        );
+    }
+
+    #[test]
+    fn relative_palette_paths_are_relative_to_the_config_directory() {
+        let directory = env::temp_dir().join(format!("fraktaloj_gui-config-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("config.ini");
+        fs::write(&path, "[render]\npalette = palettes/fire.map").unwrap();
+        let relative = Config::load(&path, true).map(|config| config.palette);
+        fs::write(&path, "[render]\npalette = /usr/share/fire.map").unwrap();
+        let absolute = Config::load(&path, true).map(|config| config.palette);
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(
+            relative.unwrap(),
+            Some(directory.join("palettes").join("fire.map"))
+        );
+        assert_eq!(
+            absolute.unwrap(),
+            Some(PathBuf::from("/usr/share/fire.map"))
+        );
+    }
+
+    #[test]
+    fn missing_files_are_only_an_error_when_required() {
+        let path = env::temp_dir().join(format!("fraktaloj_gui-missing-{}.ini", process::id()));
+        assert!(Config::load(&path, false).is_ok());
+        assert!(matches!(Config::load(&path, true), Err(ConfigError::Io(_))));
+    }
+}
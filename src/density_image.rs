@@ -0,0 +1,65 @@
+use std::num::NonZeroUsize;
+
+use sdl2::render::Texture;
+
+use crate::error::{InvalidBufferSize, WriteToTextureError};
+
+/// How often orbits visited each pixel, with separate red, green and blue counts.
+pub struct DensityImage {
+    buffer: Vec<u32>,
+    width: NonZeroUsize,
+    samples: u64,
+}
+
+impl DensityImage {
+    /// `buffer` holds red, green and blue counts for every pixel; `samples` is how many orbits were
+    /// sampled to accumulate them.
+    pub fn from_buffer(
+        buffer: Vec<u32>,
+        width: NonZeroUsize,
+        samples: u64,
+    ) -> Result<Self, InvalidBufferSize> {
+        if buffer.is_empty() || !buffer.len().is_multiple_of(width.get() * 3) {
+            return Err(InvalidBufferSize);
+        }
+        Ok(Self {
+            buffer,
+            width,
+            samples,
+        })
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    pub fn write_to_texture(&self, texture: &mut Texture) -> Result<(), WriteToTextureError> {
+        let query = texture.query();
+        let height = self.buffer.len() / 3 / self.width.get();
+        if self.width.get() != query.width as usize || height != query.height as usize {
+            return Err(WriteToTextureError::DimensionsDoNotMatch);
+        }
+        // Every channel is normalised on its own so the shortest iteration limit stays visible
+        let maxima = [0, 1, 2].map(|channel| {
+            self.buffer
+                .iter()
+                .skip(channel)
+                .step_by(3)
+                .copied()
+                .max()
+                .unwrap_or(0)
+                .max(1)
+        });
+        let texture_data = self
+            .buffer
+            .iter()
+            .enumerate()
+            .map(|(index, &count)| {
+                let t = f64::from(count) / f64::from(maxima[index % 3]);
+                (t.sqrt() * 255.0) as u8
+            })
+            .collect::<Vec<_>>();
+        texture.update(None, &texture_data, self.width.get() * 3)?;
+        Ok(())
+    }
+}
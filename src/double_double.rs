@@ -0,0 +1,280 @@
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+use num::{Num, One, Zero};
+
+/// Unevaluated sum of two doubles with non-overlapping mantissas, giving about 32 significant
+/// decimal digits. Arithmetic follows the QD library by Hida, Li and Bailey.
+#[derive(PartialEq, PartialOrd, Clone, Copy, Debug, Default)]
+pub struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+/// Sum and rounding error of `a + b`, for any `a` and `b`.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let b_virtual = sum - a;
+    (sum, (a - (sum - b_virtual)) + (b - b_virtual))
+}
+
+/// Sum and rounding error of `a + b`, for `|a| >= |b|`.
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    (sum, b - (sum - a))
+}
+
+/// Product and rounding error of `a * b`.
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let product = a * b;
+    (product, a.mul_add(b, -product))
+}
+
+impl DoubleDouble {
+    pub const fn new(hi: f64, lo: f64) -> Self {
+        Self { hi, lo }
+    }
+
+    fn renormalised((hi, lo): (f64, f64)) -> Self {
+        let (hi, lo) = quick_two_sum(hi, lo);
+        Self { hi, lo }
+    }
+
+    /// Most significant part.
+    pub fn hi(self) -> f64 {
+        self.hi
+    }
+
+    /// Least significant part.
+    pub fn lo(self) -> f64 {
+        self.lo
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    pub fn abs(self) -> Self {
+        if self.hi < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    /// Rounds towards zero. An integral `hi` leaves the rounding to `lo`, which has to go the same
+    /// way as the whole value even where its own sign differs, so like QD this takes the floor of
+    /// positive values and the ceiling of negative ones.
+    pub fn trunc(self) -> Self {
+        let round = if self.hi >= 0.0 {
+            f64::floor
+        } else {
+            f64::ceil
+        };
+        let hi = round(self.hi);
+        if hi == self.hi {
+            Self::renormalised((hi, round(self.lo)))
+        } else {
+            Self { hi, lo: 0.0 }
+        }
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.hi.is_finite() && self.lo.is_finite()
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(value: f64) -> Self {
+        Self { hi: value, lo: 0.0 }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (sum, error) = two_sum(self.hi, other.hi);
+        let (lo_sum, lo_error) = two_sum(self.lo, other.lo);
+        let (sum, error) = quick_two_sum(sum, error + lo_sum);
+        Self::renormalised((sum, error + lo_error))
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let (product, error) = two_product(self.hi, other.hi);
+        Self::renormalised((product, error + self.hi * other.lo + self.lo * other.hi))
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = Self;
+
+    /// Long division, one double's worth of quotient at a time.
+    fn div(self, other: Self) -> Self {
+        let first = self.hi / other.hi;
+        let remainder = self - other * Self::from(first);
+        let second = remainder.hi / other.hi;
+        let remainder = remainder - other * Self::from(second);
+        let third = remainder.hi / other.hi;
+        Self::renormalised(quick_two_sum(first, second)) + Self::from(third)
+    }
+}
+
+impl Rem for DoubleDouble {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        self - (self / other).trunc() * other
+    }
+}
+
+impl Zero for DoubleDouble {
+    fn zero() -> Self {
+        Self::default()
+    }
+
+    fn is_zero(&self) -> bool {
+        self.hi == 0.0
+    }
+}
+
+impl One for DoubleDouble {
+    fn one() -> Self {
+        Self::from(1.0)
+    }
+}
+
+impl Num for DoubleDouble {
+    type FromStrRadixErr = <f64 as Num>::FromStrRadixErr;
+
+    /// Only as precise as a double, like any number typed by hand is in practice.
+    fn from_str_radix(source: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        f64::from_str_radix(source, radix).map(Self::from)
+    }
+}
+
+impl std::fmt::Display for DoubleDouble {
+    /// Scientific notation with every significant digit.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const DIGITS: usize = 32;
+        if !self.is_finite() || self.is_zero() {
+            return write!(f, "{}", self.hi);
+        }
+        if self.hi < 0.0 {
+            write!(f, "-")?;
+        }
+        let ten = Self::from(10.0);
+        let mut exponent = self.hi.abs().log10().floor() as i32;
+        let power = (0..exponent.unsigned_abs()).fold(Self::one(), |power, _| power * ten);
+        let mut mantissa = if exponent >= 0 {
+            self.abs() / power
+        } else {
+            self.abs() * power
+        };
+        // The logarithm may be off by one either way near powers of ten
+        if mantissa.hi >= 10.0 {
+            mantissa = mantissa / ten;
+            exponent += 1;
+        } else if mantissa.hi < 1.0 {
+            mantissa = mantissa * ten;
+            exponent -= 1;
+        }
+        for index in 0..DIGITS {
+            let digit = mantissa.trunc().to_f64().clamp(0.0, 9.0) as u8;
+            write!(f, "{digit}")?;
+            if index == 0 {
+                write!(f, ".")?;
+            }
+            mantissa = (mantissa - Self::from(f64::from(digit))) * ten;
+        }
+        write!(f, "e{exponent}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn third_is_exact_to_double_double_precision() {
+        let third = DoubleDouble::from(1.0) / DoubleDouble::from(3.0);
+        assert_eq!(third.hi(), 1.0 / 3.0);
+        assert_ne!(third.lo(), 0.0);
+        let error = third * DoubleDouble::from(3.0) - DoubleDouble::from(1.0);
+        assert!(error.to_f64().abs() < 1e-31, "1/3 * 3 - 1 = {error}");
+    }
+
+    #[test]
+    fn trunc_rounds_towards_zero() {
+        let cases = [
+            ((3.0, -1e-20), 2.0),
+            ((-3.0, 1e-20), -2.0),
+            ((3.0, 1e-20), 3.0),
+            ((-3.0, -1e-20), -3.0),
+            ((2.5, 1e-17), 2.0),
+            ((-2.5, -1e-17), -2.0),
+        ];
+        for ((hi, lo), expected) in cases {
+            let truncated = DoubleDouble::new(hi, lo).trunc();
+            assert_eq!(
+                (truncated.hi(), truncated.lo()),
+                (expected, 0.0),
+                "trunc of ({hi}, {lo})"
+            );
+        }
+        // Integral parts beyond a double's precision keep their low part
+        let truncated = DoubleDouble::new(1e20, -3.5).trunc();
+        assert_eq!((truncated.hi(), truncated.lo()), (1e20, -4.0));
+    }
+
+    #[test]
+    fn rem_keeps_the_sign_of_the_dividend() {
+        let remainder = DoubleDouble::from(7.0) % DoubleDouble::from(2.0);
+        assert_eq!((remainder.hi(), remainder.lo()), (1.0, 0.0));
+        let remainder = DoubleDouble::from(-7.0) % DoubleDouble::from(2.0);
+        assert_eq!((remainder.hi(), remainder.lo()), (-1.0, 0.0));
+        // Just below 3, so just below 1 rather than 0
+        let remainder = DoubleDouble::new(3.0, -1e-20) % DoubleDouble::from(1.0);
+        assert_eq!(remainder.hi(), 1.0);
+        assert!((remainder.lo() + 1e-20).abs() < 1e-30, "{remainder:?}");
+    }
+
+    #[test]
+    fn display_shows_every_significant_digit() {
+        let third = DoubleDouble::from(1.0) / DoubleDouble::from(3.0);
+        let shown = third.to_string();
+        assert!(
+            shown.starts_with("3.333333333333333333333333333333") && shown.ends_with("e-1"),
+            "{shown}"
+        );
+        assert_eq!(
+            DoubleDouble::from(-1250.0).to_string(),
+            format!("-1.25{}e3", "0".repeat(29))
+        );
+        let shown = DoubleDouble::new(3.0, -1e-20).to_string();
+        assert!(shown.starts_with("2.9999999999999999999"), "{shown}");
+        assert_eq!(DoubleDouble::zero().to_string(), "0");
+    }
+}
@@ -1,5 +1,6 @@
-use std::num::TryFromIntError;
+use std::{ffi::OsString, fmt, io, num::TryFromIntError, path::PathBuf};
 
+use fraktaloj_gui::external_ray::ExternalAngleError;
 use opencl3::error_codes::ClError;
 use sdl2::{
     render::{TextureValueError, UpdateTextureError},
@@ -123,6 +124,8 @@ pub enum OpenclError {
     NoDevices,
     CompileError(String),
     IntegerOverflow,
+    /// A `DeviceImage` whose samples the renderer has since overwritten.
+    StaleDeviceImage,
 }
 
 impl From<ClError> for OpenclError {
@@ -136,3 +139,119 @@ impl From<String> for OpenclError {
         Self::CompileError(value)
     }
 }
+
+#[derive(Debug)]
+pub enum PaletteError {
+    Io(io::Error),
+    UnsupportedFormat(PathBuf),
+    Syntax { line: usize, message: String },
+    Empty,
+}
+
+impl From<io::Error> for PaletteError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::UnsupportedFormat(path) => {
+                write!(f, "{} is not a .map, .ggr or .csv file", path.display())
+            }
+            Self::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            Self::Empty => write!(f, "the file contains no colours"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Syntax { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PolynomialError {
+    InvalidNumber(String),
+    Constant,
+}
+
+impl fmt::Display for PolynomialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidNumber(number) => write!(f, "{number} is not a complex number"),
+            Self::Constant => write!(f, "the polynomial must have at least one root"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SequenceError {
+    Empty,
+    TooLong,
+    InvalidLetter(char),
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "the sequence is empty"),
+            Self::TooLong => write!(f, "the sequence is longer than 64 letters"),
+            Self::InvalidLetter(letter) => write!(f, "{letter} is neither A nor B"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FormulaError {
+    /// Character the error was found at, counted from 1.
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum ArgumentError {
+    MissingValue(&'static str),
+    Unknown(OsString),
+    InvalidPolynomial(&'static str, PolynomialError),
+    InvalidSequence(SequenceError),
+    InvalidNumber(&'static str, OsString),
+    InvalidAngle(ExternalAngleError),
+}
+
+impl fmt::Display for ArgumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingValue(option) => write!(f, "{option} requires a value"),
+            Self::Unknown(argument) => write!(f, "unknown argument {}", argument.to_string_lossy()),
+            Self::InvalidPolynomial(option, error) => write!(f, "{option}: {error}"),
+            Self::InvalidSequence(error) => write!(f, "--lyapunov-sequence: {error}"),
+            Self::InvalidAngle(error) => write!(f, "--ray: {error}"),
+            Self::InvalidNumber(option, value) => {
+                write!(
+                    f,
+                    "{option}: {} is not a valid number",
+                    value.to_string_lossy()
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use std::{f64::consts::TAU, str::FromStr};
+
+use num::{Complex, Zero};
+
+/// Radius beyond which the Böttcher coordinate is close enough to the iterate itself.
+const ESCAPE_RADIUS: f64 = 65536.0;
+
+/// Points traced per doubling of the angle; more give smoother and more reliable rays.
+const SHARPNESS: u32 = 8;
+
+const MAX_NEWTON_STEPS: u32 = 16;
+
+/// Squared relative Newton step size at which a point counts as found.
+const NEWTON_TOLERANCE: f64 = 1e-24;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ExternalAngleError {
+    MissingPoint,
+    InvalidDigit(char),
+    UnclosedPeriod,
+    TrailingCharacters,
+}
+
+impl std::fmt::Display for ExternalAngleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingPoint => write!(f, "angles must start with \"0.\" or \".\""),
+            Self::InvalidDigit(digit) => write!(f, "{digit} is not a binary digit"),
+            Self::UnclosedPeriod => write!(f, "missing \")\" after the periodic digits"),
+            Self::TrailingCharacters => write!(f, "nothing may follow the periodic digits"),
+        }
+    }
+}
+
+/// Angle in turns, written in binary with the repeating digits in parentheses, so `0.(01)` is 1/3
+/// and `0.1(0)`, or just `0.1`, is 1/2. Rational angles have exact expansions, which keeps
+/// doubling them exact however deep a ray is traced.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct ExternalAngle {
+    preperiod: Vec<bool>,
+    period: Vec<bool>,
+}
+
+impl ExternalAngle {
+    /// Terminating expansion with the given digits after the point.
+    pub fn from_bits(bits: Vec<bool>) -> Self {
+        Self {
+            preperiod: bits,
+            period: Vec::new(),
+        }
+    }
+
+    /// Digit `index` after the point, counting from 0.
+    pub fn bit(&self, index: usize) -> bool {
+        match self.preperiod.get(index) {
+            Some(&bit) => bit,
+            None if self.period.is_empty() => false,
+            None => self.period[(index - self.preperiod.len()) % self.period.len()],
+        }
+    }
+
+    /// Twice the angle, modulo a full turn.
+    pub fn doubled(&self) -> Self {
+        match self.preperiod.split_first() {
+            Some((_, rest)) => Self {
+                preperiod: rest.to_vec(),
+                period: self.period.clone(),
+            },
+            None => {
+                let mut period = self.period.clone();
+                if !period.is_empty() {
+                    period.rotate_left(1);
+                }
+                Self {
+                    preperiod: Vec::new(),
+                    period,
+                }
+            }
+        }
+    }
+
+    pub fn turns(&self) -> f64 {
+        (0..f64::MANTISSA_DIGITS as usize + 1)
+            .filter(|&index| self.bit(index))
+            .fold(0.0, |turns, index| turns + 0.5_f64.powi(index as i32 + 1))
+    }
+}
+
+impl FromStr for ExternalAngle {
+    type Err = ExternalAngleError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let digits = source
+            .strip_prefix("0.")
+            .or_else(|| source.strip_prefix('.'))
+            .ok_or(ExternalAngleError::MissingPoint)?;
+        let parse_bits = |digits: &str| {
+            digits
+                .chars()
+                .map(|digit| match digit {
+                    '0' => Ok(false),
+                    '1' => Ok(true),
+                    _ => Err(ExternalAngleError::InvalidDigit(digit)),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        match digits.split_once('(') {
+            None => Ok(Self::from_bits(parse_bits(digits)?)),
+            Some((preperiod, rest)) => {
+                let (period, trailing) = rest
+                    .split_once(')')
+                    .ok_or(ExternalAngleError::UnclosedPeriod)?;
+                if !trailing.is_empty() {
+                    return Err(ExternalAngleError::TrailingCharacters);
+                }
+                Ok(Self {
+                    preperiod: parse_bits(preperiod)?,
+                    period: parse_bits(period)?,
+                })
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ExternalAngle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = |bits: &[bool]| {
+            bits.iter()
+                .map(|&bit| if bit { '1' } else { '0' })
+                .collect::<String>()
+        };
+        write!(f, "0.{}", digits(&self.preperiod))?;
+        if !self.period.is_empty() {
+            write!(f, "({})", digits(&self.period))?;
+        }
+        Ok(())
+    }
+}
+
+/// The `iterations`-th iterate of 0 under z² + c and its derivative with respect to c.
+fn iterate(c: Complex<f64>, iterations: u32) -> (Complex<f64>, Complex<f64>) {
+    let mut z = Complex::zero();
+    let mut derivative = Complex::zero();
+    for _ in 0..iterations {
+        derivative = z * derivative * 2.0 + 1.0;
+        z = z * z + c;
+    }
+    (z, derivative)
+}
+
+/// Moves `guess` to where the `iterations`-th iterate of 0 lands on `target`, using Newton's method.
+fn solve(guess: Complex<f64>, iterations: u32, target: Complex<f64>) -> Option<Complex<f64>> {
+    let mut c = guess;
+    for _ in 0..MAX_NEWTON_STEPS {
+        let (z, derivative) = iterate(c, iterations);
+        let step = (z - target) / derivative;
+        if !step.is_finite() {
+            return None;
+        }
+        c -= step;
+        if step.norm_sqr() <= c.norm_sqr() * NEWTON_TOLERANCE {
+            break;
+        }
+    }
+    Some(c)
+}
+
+/// Points along the external ray at `angle`, from far outside the set inwards until consecutive
+/// points are closer than `precision` or the angle has been doubled `max_depth` times.
+pub fn trace_ray(angle: &ExternalAngle, precision: f64, max_depth: u32) -> Vec<Complex<f64>> {
+    let mut angle = angle.clone();
+    let mut c = Complex::from_polar(ESCAPE_RADIUS, TAU * angle.turns());
+    let mut points = vec![c];
+    for depth in 0..max_depth {
+        let direction = TAU * angle.turns();
+        for step in 0..SHARPNESS {
+            // The iterate's potential halves with every doubling, so its radius shrinks to the
+            // square root of the escape radius over one depth
+            let exponent = 0.5_f64.powf((f64::from(step) + 0.5) / f64::from(SHARPNESS));
+            let target = Complex::from_polar(ESCAPE_RADIUS.powf(exponent), direction);
+            let Some(next) = solve(c, depth + 1, target) else {
+                return points;
+            };
+            let distance = (next - c).norm();
+            c = next;
+            points.push(c);
+            if distance < precision {
+                return points;
+            }
+        }
+        angle = angle.doubled();
+    }
+    points
+}
+
+/// Whether the argument of `z` lies in the half turn from π up to 2π, making the binary digit of
+/// its angle in turns 1. The negative real axis belongs to it, being at exactly half a turn.
+fn in_upper_half_turn(z: Complex<f64>) -> bool {
+    z.im < 0.0 || (z.im == 0.0 && z.re < 0.0)
+}
+
+/// Estimates the external angle of `c` by tracing its ray outwards, reading a binary digit off the
+/// half plane the iterate lies in each time the ray climbs past a level. Gives `None` for points
+/// that do not escape within `max_iterations`.
+pub fn external_angle(c: Complex<f64>, max_iterations: u32) -> Option<ExternalAngle> {
+    let mut z = Complex::<f64>::zero();
+    let mut escape_time = 0;
+    while z.norm_sqr() < ESCAPE_RADIUS * ESCAPE_RADIUS {
+        if escape_time == max_iterations {
+            return None;
+        }
+        z = z * z + c;
+        escape_time += 1;
+    }
+    if escape_time == 1 {
+        // Already as far out as rays are traced, where c's own argument gives the angle
+        return Some(ExternalAngle::from_bits(vec![in_upper_half_turn(c)]));
+    }
+    let mut bits = vec![false; escape_time as usize - 1];
+    let mut c = c;
+    for level in (1..escape_time).rev() {
+        // Climbs from the iterate's current radius to the escape radius evenly in potential
+        let start = iterate(c, level).0.norm().max(2.0);
+        for step in 1..=SHARPNESS {
+            let (z, _) = iterate(c, level);
+            let t = f64::from(step) / f64::from(SHARPNESS);
+            let radius = start.powf((ESCAPE_RADIUS.ln() / start.ln()).powf(t));
+            // Scaling the iterate rather than going through its argument keeps real iterates real,
+            // which from_polar would knock off the negative real axis
+            c = solve(c, level, z * (radius / z.norm()))?;
+        }
+        // With the iterate this large its argument is 2π times the angle doubled level - 1 times
+        bits[level as usize - 1] = in_upper_half_turn(iterate(c, level).0);
+    }
+    Some(ExternalAngle::from_bits(bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_periodic_angles() {
+        let third: ExternalAngle = "0.(01)".parse().unwrap();
+        assert!((third.turns() - 1.0 / 3.0).abs() < 1e-15);
+        assert_eq!(third.doubled().doubled(), third);
+        assert_eq!(
+            ".1".parse::<ExternalAngle>().unwrap().turns(),
+            "0.1(0)".parse::<ExternalAngle>().unwrap().turns()
+        );
+        assert_eq!(
+            "0.(01".parse::<ExternalAngle>(),
+            Err(ExternalAngleError::UnclosedPeriod)
+        );
+        assert_eq!(
+            "0.012".parse::<ExternalAngle>(),
+            Err(ExternalAngleError::InvalidDigit('2'))
+        );
+    }
+
+    #[test]
+    fn third_ray_lands_on_the_period_two_root() {
+        let angle = "0.(01)".parse().unwrap();
+        let ray = trace_ray(&angle, 1e-6, 1000);
+        let end = *ray.last().unwrap();
+        // Rays approach parabolic points slowly
+        assert!((end - Complex::new(-0.75, 0.0)).norm() < 0.01, "{end}");
+    }
+
+    #[test]
+    fn angle_on_the_negative_real_axis_is_a_half() {
+        let angle = external_angle(Complex::new(-2.1, 0.0), 1000).unwrap();
+        assert!((angle.turns() - 0.5).abs() < 1e-6, "{}", angle.turns());
+    }
+
+    #[test]
+    fn angle_beyond_the_escape_radius_follows_the_argument() {
+        let far = 2.0 * ESCAPE_RADIUS;
+        let angle = |c| external_angle(c, 1000).unwrap().turns();
+        assert_eq!(angle(Complex::new(far, 0.0)), 0.0);
+        assert_eq!(angle(Complex::new(0.0, far)), 0.0);
+        assert_eq!(angle(Complex::new(-far, 0.0)), 0.5);
+        assert_eq!(angle(Complex::new(0.0, -far)), 0.5);
+    }
+
+    #[test]
+    fn interior_points_have_no_angle() {
+        assert_eq!(external_angle(Complex::new(-0.1, 0.1), 1000), None);
+    }
+}
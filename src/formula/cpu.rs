@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use num::Complex;
+
+use super::{integer_exponent, BinaryOperator, Expression, Variable};
+
+type Closure = Box<dyn Fn(Complex<f64>, Complex<f64>) -> Complex<f64> + Send + Sync>;
+
+/// A formula turned into nested closures taking `z` and `c`, cheap to clone and share between
+/// threads.
+#[derive(Clone)]
+pub struct CompiledFormula(Arc<Closure>);
+
+impl CompiledFormula {
+    pub fn evaluate(&self, z: Complex<f64>, c: Complex<f64>) -> Complex<f64> {
+        (self.0)(z, c)
+    }
+}
+
+pub fn compile(expression: &Expression) -> CompiledFormula {
+    CompiledFormula(Arc::new(closure(expression)))
+}
+
+fn closure(expression: &Expression) -> Closure {
+    match expression {
+        &Expression::Constant(value) => Box::new(move |_, _| value),
+        Expression::Variable(Variable::Z) => Box::new(|z, _| z),
+        Expression::Variable(Variable::C) => Box::new(|_, c| c),
+        Expression::Negate(operand) => {
+            let operand = closure(operand);
+            Box::new(move |z, c| -operand(z, c))
+        }
+        &Expression::Binary(operator, ref left, ref right) => {
+            let left = closure(left);
+            // The exponent was folded into a constant if it does not depend on a variable
+            if let (BinaryOperator::Power, &Expression::Constant(exponent)) = (operator, &**right) {
+                if let Some(exponent) = integer_exponent(exponent) {
+                    return Box::new(move |z, c| left(z, c).powi(exponent));
+                }
+            }
+            let right = closure(right);
+            Box::new(move |z, c| operator.apply(left(z, c), right(z, c)))
+        }
+        &Expression::Function(function, ref argument) => {
+            let argument = closure(argument);
+            Box::new(move |z, c| function.apply(argument(z, c)))
+        }
+        Expression::Conditional(condition, then, otherwise) => {
+            let (condition, then, otherwise) =
+                (closure(condition), closure(then), closure(otherwise));
+            Box::new(move |z, c| {
+                if condition(z, c).re != 0.0 {
+                    then(z, c)
+                } else {
+                    otherwise(z, c)
+                }
+            })
+        }
+    }
+}
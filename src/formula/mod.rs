@@ -0,0 +1,224 @@
+use num::Complex;
+
+use crate::error::FormulaError;
+
+mod cpu;
+mod opencl;
+mod parser;
+
+pub use cpu::CompiledFormula;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Variable {
+    Z,
+    C,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    /// Comparisons look at real parts only and evaluate to 1 or 0.
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    /// Equality compares both parts and evaluates to 1 or 0.
+    Equal,
+    NotEqual,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Log,
+    Sqrt,
+    /// Modulus, as a real number.
+    Abs,
+    /// Squared modulus, as a real number.
+    Norm,
+    Arg,
+    Re,
+    Im,
+    Conj,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sin" => Self::Sin,
+            "cos" => Self::Cos,
+            "tan" => Self::Tan,
+            "sinh" => Self::Sinh,
+            "cosh" => Self::Cosh,
+            "tanh" => Self::Tanh,
+            "exp" => Self::Exp,
+            "log" => Self::Log,
+            "sqrt" => Self::Sqrt,
+            "abs" => Self::Abs,
+            "norm" => Self::Norm,
+            "arg" => Self::Arg,
+            "re" => Self::Re,
+            "im" => Self::Im,
+            "conj" => Self::Conj,
+            _ => return None,
+        })
+    }
+
+    fn apply(self, z: Complex<f64>) -> Complex<f64> {
+        match self {
+            Self::Sin => z.sin(),
+            Self::Cos => z.cos(),
+            Self::Tan => z.tan(),
+            Self::Sinh => z.sinh(),
+            Self::Cosh => z.cosh(),
+            Self::Tanh => z.tanh(),
+            Self::Exp => z.exp(),
+            Self::Log => z.ln(),
+            Self::Sqrt => z.sqrt(),
+            Self::Abs => Complex::new(z.norm(), 0.0),
+            Self::Norm => Complex::new(z.norm_sqr(), 0.0),
+            Self::Arg => Complex::new(z.arg(), 0.0),
+            Self::Re => Complex::new(z.re, 0.0),
+            Self::Im => Complex::new(z.im, 0.0),
+            Self::Conj => z.conj(),
+        }
+    }
+}
+
+impl BinaryOperator {
+    fn apply(self, a: Complex<f64>, b: Complex<f64>) -> Complex<f64> {
+        let truth = |condition: bool| Complex::new(if condition { 1.0 } else { 0.0 }, 0.0);
+        match self {
+            Self::Add => a + b,
+            Self::Subtract => a - b,
+            Self::Multiply => a * b,
+            Self::Divide => a / b,
+            Self::Power => match integer_exponent(b) {
+                Some(exponent) => a.powi(exponent),
+                None => a.powc(b),
+            },
+            Self::Less => truth(a.re < b.re),
+            Self::LessOrEqual => truth(a.re <= b.re),
+            Self::Greater => truth(a.re > b.re),
+            Self::GreaterOrEqual => truth(a.re >= b.re),
+            Self::Equal => truth(a == b),
+            Self::NotEqual => truth(a != b),
+        }
+    }
+}
+
+/// Largest exponent raised by repeated multiplication rather than through logarithms.
+const MAX_INTEGER_EXPONENT: f64 = 64.0;
+
+/// Exponents that are small integers, which both backends raise to by repeated multiplication.
+fn integer_exponent(exponent: Complex<f64>) -> Option<i32> {
+    (exponent.im == 0.0 && exponent.re.fract() == 0.0 && exponent.re.abs() <= MAX_INTEGER_EXPONENT)
+        .then_some(exponent.re as i32)
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Expression {
+    Constant(Complex<f64>),
+    Variable(Variable),
+    Negate(Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+    Function(Function, Box<Expression>),
+    /// Picks the second expression where the first has a non-zero real part, else the third.
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Evaluates every subexpression that does not depend on a variable.
+    fn fold_constants(self) -> Self {
+        match self {
+            Self::Negate(operand) => match operand.fold_constants() {
+                Self::Constant(value) => Self::Constant(-value),
+                operand => Self::Negate(Box::new(operand)),
+            },
+            Self::Binary(operator, left, right) => {
+                match (left.fold_constants(), right.fold_constants()) {
+                    (Self::Constant(left), Self::Constant(right)) => {
+                        Self::Constant(operator.apply(left, right))
+                    }
+                    (left, right) => Self::Binary(operator, Box::new(left), Box::new(right)),
+                }
+            }
+            Self::Function(function, argument) => match argument.fold_constants() {
+                Self::Constant(value) => Self::Constant(function.apply(value)),
+                argument => Self::Function(function, Box::new(argument)),
+            },
+            Self::Conditional(condition, then, otherwise) => match condition.fold_constants() {
+                Self::Constant(value) if value.re != 0.0 => then.fold_constants(),
+                Self::Constant(_) => otherwise.fold_constants(),
+                condition => Self::Conditional(
+                    Box::new(condition),
+                    Box::new(then.fold_constants()),
+                    Box::new(otherwise.fold_constants()),
+                ),
+            },
+            expression => expression,
+        }
+    }
+}
+
+/// Iteration step `z ↦ f(z, c)` replacing z² + c, written like `z^3 + c*sin(z)`.
+///
+/// Numbers, `i`, `pi` and `e` are constants, `z` and `c` variables. Besides `+ - * / ^` there
+/// are the comparisons `< <= > >= == !=` and conditionals `condition ? then : otherwise`, as well
+/// as the functions `sin cos tan sinh cosh tanh exp log sqrt abs norm arg re im conj`.
+#[derive(Clone, Debug)]
+pub struct Formula {
+    source: String,
+    expression: Expression,
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Self, FormulaError> {
+        Ok(Self {
+            source: source.to_string(),
+            expression: parser::parse(source)?.fold_constants(),
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Turns the formula into nested closures for the CPU renderer.
+    pub fn compile(&self) -> CompiledFormula {
+        cpu::compile(&self.expression)
+    }
+
+    /// Defines the formula as `double2 next_z(double2 z, double2 c)` for `kernel.cl`.
+    pub fn to_opencl(&self) -> String {
+        format!(
+            "double2 next_z(double2 z, double2 c) {{\n    return {};\n}}\n",
+            opencl::transpile(&self.expression)
+        )
+    }
+}
+
+impl PartialEq for Formula {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+fn constant(name: &str) -> Option<Complex<f64>> {
+    Some(match name {
+        "i" => Complex::i(),
+        "pi" => Complex::new(std::f64::consts::PI, 0.0),
+        "e" => Complex::new(std::f64::consts::E, 0.0),
+        _ => return None,
+    })
+}
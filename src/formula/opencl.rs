@@ -0,0 +1,77 @@
+use super::{integer_exponent, BinaryOperator, Expression, Function, Variable};
+
+fn real(value: f64) -> String {
+    if value.is_nan() {
+        "NAN".to_string()
+    } else if value.is_infinite() {
+        format!("{}INFINITY", if value < 0.0 { "-" } else { "" })
+    } else {
+        // Debug formatting always includes a decimal point or exponent, keeping literals doubles
+        format!("{value:?}")
+    }
+}
+
+/// Turns an expression into OpenCL C operating on `double2` values with the helpers of
+/// `complex.cl`.
+pub fn transpile(expression: &Expression) -> String {
+    match expression {
+        Expression::Constant(value) => {
+            format!("((double2) ({}, {}))", real(value.re), real(value.im))
+        }
+        Expression::Variable(Variable::Z) => "z".to_string(),
+        Expression::Variable(Variable::C) => "c".to_string(),
+        Expression::Negate(operand) => format!("(-{})", transpile(operand)),
+        Expression::Binary(operator, left, right) => {
+            let (left, right) = match (operator, &**right) {
+                (BinaryOperator::Power, &Expression::Constant(exponent)) => {
+                    if let Some(exponent) = integer_exponent(exponent) {
+                        return format!("complex_powi({}, {exponent})", transpile(left));
+                    }
+                    (transpile(left), transpile(right))
+                }
+                _ => (transpile(left), transpile(right)),
+            };
+            match operator {
+                BinaryOperator::Add => format!("({left} + {right})"),
+                BinaryOperator::Subtract => format!("({left} - {right})"),
+                BinaryOperator::Multiply => format!("complex_multiply({left}, {right})"),
+                BinaryOperator::Divide => format!("complex_divide({left}, {right})"),
+                BinaryOperator::Power => format!("complex_pow({left}, {right})"),
+                BinaryOperator::Less => format!("complex_less({left}, {right})"),
+                BinaryOperator::LessOrEqual => format!("complex_less_or_equal({left}, {right})"),
+                BinaryOperator::Greater => format!("complex_less({right}, {left})"),
+                BinaryOperator::GreaterOrEqual => {
+                    format!("complex_less_or_equal({right}, {left})")
+                }
+                BinaryOperator::Equal => format!("complex_equal({left}, {right})"),
+                BinaryOperator::NotEqual => format!("complex_not_equal({left}, {right})"),
+            }
+        }
+        Expression::Function(function, argument) => {
+            let name = match function {
+                Function::Sin => "sin",
+                Function::Cos => "cos",
+                Function::Tan => "tan",
+                Function::Sinh => "sinh",
+                Function::Cosh => "cosh",
+                Function::Tanh => "tanh",
+                Function::Exp => "exp",
+                Function::Log => "log",
+                Function::Sqrt => "sqrt",
+                Function::Abs => "abs",
+                Function::Norm => "norm",
+                Function::Arg => "arg",
+                Function::Re => "re",
+                Function::Im => "im",
+                Function::Conj => "conj",
+            };
+            format!("complex_{name}({})", transpile(argument))
+        }
+        Expression::Conditional(condition, then, otherwise) => format!(
+            "({}.x != 0.0 ? {} : {})",
+            transpile(condition),
+            transpile(then),
+            transpile(otherwise)
+        ),
+    }
+}
@@ -0,0 +1,285 @@
+//! Recursive descent parser, from the loosest binding construct to the tightest:
+//!
+//! ```text
+//! expression     = comparison [ "?" expression ":" expression ]
+//! comparison     = sum [ ( "<" | "<=" | ">" | ">=" | "==" | "!=" ) sum ]
+//! sum            = product { ( "+" | "-" ) product }
+//! product        = unary { ( "*" | "/" ) unary }
+//! unary          = "-" unary | power
+//! power          = primary [ "^" unary ]
+//! primary        = number | name | name "(" expression ")" | "(" expression ")"
+//! ```
+
+use num::Complex;
+
+use crate::error::FormulaError;
+
+use super::{constant, BinaryOperator, Expression, Function, Variable};
+
+#[derive(PartialEq, Clone, Debug)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{number}"),
+            Self::Name(name) => write!(f, "{name}"),
+            Self::Symbol(symbol) => write!(f, "{symbol}"),
+        }
+    }
+}
+
+/// Longest symbols first, so `<=` is not read as `<` followed by `=`.
+const SYMBOLS: [&str; 15] = [
+    "<=", ">=", "==", "!=", "+", "-", "*", "/", "^", "(", ")", "<", ">", "?", ":",
+];
+
+/// Splits the source into tokens along with their column, counted from 1.
+fn tokenise(source: &str) -> Result<Vec<(Token, usize)>, FormulaError> {
+    let characters = source.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < characters.len() {
+        let column = index + 1;
+        let character = characters[index];
+        if character.is_whitespace() {
+            index += 1;
+        } else if character.is_ascii_digit() || character == '.' {
+            let start = index;
+            while index < characters.len()
+                && (characters[index].is_ascii_digit() || characters[index] == '.')
+            {
+                index += 1;
+            }
+            // Exponents such as 1e-3
+            if index < characters.len() && matches!(characters[index], 'e' | 'E') {
+                let mut end = index + 1;
+                if end < characters.len() && matches!(characters[end], '+' | '-') {
+                    end += 1;
+                }
+                if end < characters.len() && characters[end].is_ascii_digit() {
+                    index = end;
+                    while index < characters.len() && characters[index].is_ascii_digit() {
+                        index += 1;
+                    }
+                }
+            }
+            let text = characters[start..index].iter().collect::<String>();
+            let number = text.parse().map_err(|_| FormulaError {
+                column,
+                message: format!("{text} is not a number"),
+            })?;
+            tokens.push((Token::Number(number), column));
+        } else if character.is_alphabetic() || character == '_' {
+            let start = index;
+            while index < characters.len()
+                && (characters[index].is_alphanumeric() || characters[index] == '_')
+            {
+                index += 1;
+            }
+            let name = characters[start..index].iter().collect();
+            tokens.push((Token::Name(name), column));
+        } else {
+            let rest = characters[index..].iter().take(2).collect::<String>();
+            let symbol = SYMBOLS
+                .into_iter()
+                .find(|symbol| rest.starts_with(symbol))
+                .ok_or_else(|| FormulaError {
+                    column,
+                    message: format!("unexpected character {character}"),
+                })?;
+            tokens.push((Token::Symbol(symbol), column));
+            index += symbol.len();
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// Column just past the end of the source, reported for errors at the end.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end, |&(_, column)| column)
+    }
+
+    fn error(&self, message: impl Into<String>) -> FormulaError {
+        FormulaError {
+            column: self.column(),
+            message: message.into(),
+        }
+    }
+
+    /// Consumes the next token if it is `symbol`.
+    fn accept(&mut self, symbol: &'static str) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), FormulaError> {
+        if self.accept(symbol) {
+            Ok(())
+        } else {
+            Err(match self.peek() {
+                Some(token) => self.error(format!("expected {symbol} but found {token}")),
+                None => self.error(format!("expected {symbol}")),
+            })
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression, FormulaError> {
+        let condition = self.comparison()?;
+        if !self.accept("?") {
+            return Ok(condition);
+        }
+        let then = self.expression()?;
+        self.expect(":")?;
+        let otherwise = self.expression()?;
+        Ok(Expression::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn comparison(&mut self) -> Result<Expression, FormulaError> {
+        let left = self.sum()?;
+        let operator = match self.peek() {
+            Some(Token::Symbol("<")) => BinaryOperator::Less,
+            Some(Token::Symbol("<=")) => BinaryOperator::LessOrEqual,
+            Some(Token::Symbol(">")) => BinaryOperator::Greater,
+            Some(Token::Symbol(">=")) => BinaryOperator::GreaterOrEqual,
+            Some(Token::Symbol("==")) => BinaryOperator::Equal,
+            Some(Token::Symbol("!=")) => BinaryOperator::NotEqual,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+        let right = self.sum()?;
+        Ok(Expression::Binary(
+            operator,
+            Box::new(left),
+            Box::new(right),
+        ))
+    }
+
+    fn sum(&mut self) -> Result<Expression, FormulaError> {
+        let mut left = self.product()?;
+        loop {
+            let operator = if self.accept("+") {
+                BinaryOperator::Add
+            } else if self.accept("-") {
+                BinaryOperator::Subtract
+            } else {
+                return Ok(left);
+            };
+            let right = self.product()?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expression, FormulaError> {
+        let mut left = self.unary()?;
+        loop {
+            let operator = if self.accept("*") {
+                BinaryOperator::Multiply
+            } else if self.accept("/") {
+                BinaryOperator::Divide
+            } else {
+                return Ok(left);
+            };
+            let right = self.unary()?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression, FormulaError> {
+        if self.accept("-") {
+            Ok(Expression::Negate(Box::new(self.unary()?)))
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Result<Expression, FormulaError> {
+        let base = self.primary()?;
+        if !self.accept("^") {
+            return Ok(base);
+        }
+        // Right associative, so z^2^3 is z^(2^3)
+        let exponent = self.unary()?;
+        Ok(Expression::Binary(
+            BinaryOperator::Power,
+            Box::new(base),
+            Box::new(exponent),
+        ))
+    }
+
+    fn primary(&mut self) -> Result<Expression, FormulaError> {
+        let column = self.column();
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.error("unexpected end of formula"));
+        };
+        self.position += 1;
+        match token {
+            Token::Number(number) => Ok(Expression::Constant(Complex::new(number, 0.0))),
+            Token::Symbol("(") => {
+                let expression = self.expression()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            Token::Name(name) => {
+                if let Some(function) = Function::from_name(&name) {
+                    self.expect("(")?;
+                    let argument = self.expression()?;
+                    self.expect(")")?;
+                    return Ok(Expression::Function(function, Box::new(argument)));
+                }
+                match name.as_str() {
+                    "z" => Ok(Expression::Variable(Variable::Z)),
+                    "c" => Ok(Expression::Variable(Variable::C)),
+                    _ => constant(&name)
+                        .map(Expression::Constant)
+                        .ok_or_else(|| FormulaError {
+                            column,
+                            message: format!("unknown name {name}"),
+                        }),
+                }
+            }
+            Token::Symbol(symbol) => Err(FormulaError {
+                column,
+                message: format!("unexpected {symbol}"),
+            }),
+        }
+    }
+}
+
+pub fn parse(source: &str) -> Result<Expression, FormulaError> {
+    let mut parser = Parser {
+        tokens: tokenise(source)?,
+        position: 0,
+        end: source.chars().count() + 1,
+    };
+    let expression = parser.expression()?;
+    match parser.peek() {
+        None => Ok(expression),
+        Some(token) => Err(parser.error(format!("unexpected {token}"))),
+    }
+}
@@ -2,43 +2,125 @@ use std::num::NonZeroUsize;
 
 use sdl2::render::Texture;
 
-use crate::error::{InvalidBufferSize, WriteToTextureError};
+use crate::{
+    colouring::{Colouring, Sample},
+    error::{InvalidBufferSize, WriteToTextureError},
+    render::orbit_trap::TrapHit,
+};
 
 pub struct IterationImage {
     buffer: Vec<u32>,
+    distances: Option<Vec<f32>>,
+    normals: Option<Vec<[f32; 2]>>,
+    trap_hits: Option<Vec<TrapHit>>,
     width: NonZeroUsize,
+    samples_per_pixel: NonZeroUsize,
     max_iterations: u32,
 }
 
 impl IterationImage {
+    /// `buffer` holds `samples_per_pixel` consecutive iteration counts for every pixel.
     pub fn from_buffer(
         buffer: Vec<u32>,
         width: NonZeroUsize,
+        samples_per_pixel: NonZeroUsize,
         max_iterations: u32,
     ) -> Result<Self, InvalidBufferSize> {
-        if buffer.is_empty() || buffer.len() % width.get() != 0 {
+        let row_length = width
+            .checked_mul(samples_per_pixel)
+            .ok_or(InvalidBufferSize)?;
+        if buffer.is_empty() || !buffer.len().is_multiple_of(row_length.get()) {
             return Err(InvalidBufferSize);
         }
         Ok(Self {
             buffer,
+            distances: None,
+            normals: None,
+            trap_hits: None,
             width,
+            samples_per_pixel,
             max_iterations,
         })
     }
 
-    pub fn write_to_texture(&self, texture: &mut Texture) -> Result<(), WriteToTextureError> {
+    /// Attaches an exterior distance estimate, in pixels, to every sample.
+    pub fn with_distances(mut self, distances: Vec<f32>) -> Result<Self, InvalidBufferSize> {
+        if distances.len() != self.buffer.len() {
+            return Err(InvalidBufferSize);
+        }
+        self.distances = Some(distances);
+        Ok(self)
+    }
+
+    /// Attaches a unit normal of the potential's level curves to every sample.
+    pub fn with_normals(mut self, normals: Vec<[f32; 2]>) -> Result<Self, InvalidBufferSize> {
+        if normals.len() != self.buffer.len() {
+            return Err(InvalidBufferSize);
+        }
+        self.normals = Some(normals);
+        Ok(self)
+    }
+
+    /// Attaches every orbit's closest approach to the orbit trap.
+    pub fn with_trap_hits(mut self, trap_hits: Vec<TrapHit>) -> Result<Self, InvalidBufferSize> {
+        if trap_hits.len() != self.buffer.len() {
+            return Err(InvalidBufferSize);
+        }
+        self.trap_hits = Some(trap_hits);
+        Ok(self)
+    }
+
+    pub fn max_iterations(&self) -> u32 {
+        self.max_iterations
+    }
+
+    pub fn width(&self) -> NonZeroUsize {
+        self.width
+    }
+
+    pub fn samples_per_pixel(&self) -> NonZeroUsize {
+        self.samples_per_pixel
+    }
+
+    /// Iteration counts of every sample, `samples_per_pixel` consecutive ones per pixel.
+    pub fn iterations(&self) -> &[u32] {
+        &self.buffer
+    }
+
+    pub(crate) fn sample(&self, index: usize) -> Sample {
+        Sample {
+            iterations: self.buffer[index],
+            max_iterations: self.max_iterations,
+            distance: self.distances.as_ref().map(|distances| distances[index]),
+            normal: self.normals.as_ref().map(|normals| normals[index]),
+            trap: self.trap_hits.as_ref().map(|trap_hits| trap_hits[index]),
+        }
+    }
+
+    pub fn write_to_texture(
+        &self,
+        texture: &mut Texture,
+        colouring: &Colouring,
+    ) -> Result<(), WriteToTextureError> {
         let query = texture.query();
-        let height = self.buffer.len() / self.width.get();
+        let height = self.buffer.len() / self.samples_per_pixel.get() / self.width.get();
         if self.width.get() != query.width as usize || height != query.height as usize {
             return Err(WriteToTextureError::DimensionsDoNotMatch);
         }
-        let max_iterations = f64::from(self.max_iterations);
-        let texture_data = self
-            .buffer
-            .iter()
-            .flat_map(|&iter_count| {
-                let t = (f64::from(iter_count) * 255.0 / max_iterations) as u8;
-                [t; 3]
+        let samples_per_pixel = self.samples_per_pixel.get();
+        let texture_data = (0..self.buffer.len())
+            .step_by(samples_per_pixel)
+            .flat_map(|first_sample| {
+                // Averaging colours rather than iteration counts keeps palette bands intact
+                let sum = (first_sample..first_sample + samples_per_pixel).fold(
+                    [0_u32; 3],
+                    |sum, index| {
+                        let colour = colouring.colour(self.sample(index));
+                        [0, 1, 2].map(|channel| sum[channel] + u32::from(colour[channel]))
+                    },
+                );
+                let samples_per_pixel = samples_per_pixel as u32;
+                sum.map(|channel| ((channel + samples_per_pixel / 2) / samples_per_pixel) as u8)
             })
             .collect::<Vec<_>>();
         texture.update(None, &texture_data, self.width.get() * 3)?;
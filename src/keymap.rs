@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use sdl2::keyboard::{KeyboardState, Scancode};
+
+/// Everything a key can be bound to. Keys that edit the formula while it is being typed are not
+/// actions and cannot be rebound.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Action {
+    PanUp,
+    PanLeft,
+    PanDown,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    Home,
+    UseCpu,
+    UseOpencl,
+    NextComparisonView,
+    DoubleIterations,
+    HalveIterations,
+    ToggleAdaptiveIterations,
+    NextFractal,
+    IncreaseShapeParameter,
+    DecreaseShapeParameter,
+    QuaternionJuliaFromView,
+    FindMinibrot,
+    FindMisiurewiczPoint,
+    EstimateExternalAngle,
+    RotateAnticlockwise,
+    RotateClockwise,
+    DecreaseStretch,
+    IncreaseStretch,
+    DecreaseSkew,
+    IncreaseSkew,
+    ToggleJuliaPreview,
+    ToggleMinimap,
+    ToggleOrbit,
+    ToggleRays,
+    ToggleSliceAnimation,
+    ToggleSoftShadows,
+    ToggleAmbientOcclusion,
+    EditFormula,
+    NextSupersampling,
+    ToggleAdaptiveSupersampling,
+    NextShading,
+    ToggleHistogramLock,
+    ToggleColourCycling,
+    FasterColourCycling,
+    SlowerColourCycling,
+    ReverseColourCycling,
+    ToggleDeviceColouring,
+    NextOrbitTrap,
+    LightAnticlockwise,
+    LightClockwise,
+    RaiseLight,
+    LowerLight,
+    MoveLightAway,
+    MoveLightCloser,
+    ToggleFullscreen,
+    /// Held rather than pressed, like the rest of the camera controls of the 3D modes.
+    FlyForward,
+    FlyBackward,
+    FlyUp,
+    FlyDown,
+    TurnLeft,
+    TurnRight,
+    TurnUp,
+    TurnDown,
+}
+
+impl Action {
+    pub const ALL: [Self; 59] = [
+        Self::PanUp,
+        Self::PanLeft,
+        Self::PanDown,
+        Self::PanRight,
+        Self::ZoomIn,
+        Self::ZoomOut,
+        Self::Home,
+        Self::UseCpu,
+        Self::UseOpencl,
+        Self::NextComparisonView,
+        Self::DoubleIterations,
+        Self::HalveIterations,
+        Self::ToggleAdaptiveIterations,
+        Self::NextFractal,
+        Self::IncreaseShapeParameter,
+        Self::DecreaseShapeParameter,
+        Self::QuaternionJuliaFromView,
+        Self::FindMinibrot,
+        Self::FindMisiurewiczPoint,
+        Self::EstimateExternalAngle,
+        Self::RotateAnticlockwise,
+        Self::RotateClockwise,
+        Self::DecreaseStretch,
+        Self::IncreaseStretch,
+        Self::DecreaseSkew,
+        Self::IncreaseSkew,
+        Self::ToggleJuliaPreview,
+        Self::ToggleMinimap,
+        Self::ToggleOrbit,
+        Self::ToggleRays,
+        Self::ToggleSliceAnimation,
+        Self::ToggleSoftShadows,
+        Self::ToggleAmbientOcclusion,
+        Self::EditFormula,
+        Self::NextSupersampling,
+        Self::ToggleAdaptiveSupersampling,
+        Self::NextShading,
+        Self::ToggleHistogramLock,
+        Self::ToggleColourCycling,
+        Self::FasterColourCycling,
+        Self::SlowerColourCycling,
+        Self::ReverseColourCycling,
+        Self::ToggleDeviceColouring,
+        Self::NextOrbitTrap,
+        Self::LightAnticlockwise,
+        Self::LightClockwise,
+        Self::RaiseLight,
+        Self::LowerLight,
+        Self::MoveLightAway,
+        Self::MoveLightCloser,
+        Self::ToggleFullscreen,
+        Self::FlyForward,
+        Self::FlyBackward,
+        Self::FlyUp,
+        Self::FlyDown,
+        Self::TurnLeft,
+        Self::TurnRight,
+        Self::TurnUp,
+        Self::TurnDown,
+    ];
+
+    /// Name of the action in the `[keys]` section of the configuration file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PanUp => "pan_up",
+            Self::PanLeft => "pan_left",
+            Self::PanDown => "pan_down",
+            Self::PanRight => "pan_right",
+            Self::ZoomIn => "zoom_in",
+            Self::ZoomOut => "zoom_out",
+            Self::Home => "home",
+            Self::UseCpu => "use_cpu",
+            Self::UseOpencl => "use_opencl",
+            Self::NextComparisonView => "next_comparison_view",
+            Self::DoubleIterations => "double_iterations",
+            Self::HalveIterations => "halve_iterations",
+            Self::ToggleAdaptiveIterations => "toggle_adaptive_iterations",
+            Self::NextFractal => "next_fractal",
+            Self::IncreaseShapeParameter => "increase_shape_parameter",
+            Self::DecreaseShapeParameter => "decrease_shape_parameter",
+            Self::QuaternionJuliaFromView => "quaternion_julia_from_view",
+            Self::FindMinibrot => "find_minibrot",
+            Self::FindMisiurewiczPoint => "find_misiurewicz_point",
+            Self::EstimateExternalAngle => "estimate_external_angle",
+            Self::RotateAnticlockwise => "rotate_anticlockwise",
+            Self::RotateClockwise => "rotate_clockwise",
+            Self::DecreaseStretch => "decrease_stretch",
+            Self::IncreaseStretch => "increase_stretch",
+            Self::DecreaseSkew => "decrease_skew",
+            Self::IncreaseSkew => "increase_skew",
+            Self::ToggleJuliaPreview => "toggle_julia_preview",
+            Self::ToggleMinimap => "toggle_minimap",
+            Self::ToggleOrbit => "toggle_orbit",
+            Self::ToggleRays => "toggle_rays",
+            Self::ToggleSliceAnimation => "toggle_slice_animation",
+            Self::ToggleSoftShadows => "toggle_soft_shadows",
+            Self::ToggleAmbientOcclusion => "toggle_ambient_occlusion",
+            Self::EditFormula => "edit_formula",
+            Self::NextSupersampling => "next_supersampling",
+            Self::ToggleAdaptiveSupersampling => "toggle_adaptive_supersampling",
+            Self::NextShading => "next_shading",
+            Self::ToggleHistogramLock => "toggle_histogram_lock",
+            Self::ToggleColourCycling => "toggle_colour_cycling",
+            Self::FasterColourCycling => "faster_colour_cycling",
+            Self::SlowerColourCycling => "slower_colour_cycling",
+            Self::ReverseColourCycling => "reverse_colour_cycling",
+            Self::ToggleDeviceColouring => "toggle_device_colouring",
+            Self::NextOrbitTrap => "next_orbit_trap",
+            Self::LightAnticlockwise => "light_anticlockwise",
+            Self::LightClockwise => "light_clockwise",
+            Self::RaiseLight => "raise_light",
+            Self::LowerLight => "lower_light",
+            Self::MoveLightAway => "move_light_away",
+            Self::MoveLightCloser => "move_light_closer",
+            Self::ToggleFullscreen => "toggle_fullscreen",
+            Self::FlyForward => "fly_forward",
+            Self::FlyBackward => "fly_backward",
+            Self::FlyUp => "fly_up",
+            Self::FlyDown => "fly_down",
+            Self::TurnLeft => "turn_left",
+            Self::TurnRight => "turn_right",
+            Self::TurnUp => "turn_up",
+            Self::TurnDown => "turn_down",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// Key the action is bound to unless the configuration file says otherwise. The 2D and 3D
+    /// modes share the arrow and page keys, each using them for its own actions.
+    pub fn default_key(self) -> Scancode {
+        match self {
+            Self::PanUp | Self::FlyForward => Scancode::Up,
+            Self::PanLeft | Self::TurnLeft => Scancode::Left,
+            Self::PanDown | Self::FlyBackward => Scancode::Down,
+            Self::PanRight | Self::TurnRight => Scancode::Right,
+            Self::ZoomIn | Self::TurnUp => Scancode::PageUp,
+            Self::ZoomOut | Self::TurnDown => Scancode::PageDown,
+            Self::Home => Scancode::Home,
+            Self::UseCpu => Scancode::Num1,
+            Self::UseOpencl => Scancode::Num2,
+            Self::NextComparisonView => Scancode::Num3,
+            Self::DoubleIterations => Scancode::Period,
+            Self::HalveIterations => Scancode::Comma,
+            Self::ToggleAdaptiveIterations => Scancode::Slash,
+            Self::NextFractal => Scancode::M,
+            Self::IncreaseShapeParameter => Scancode::Equals,
+            Self::DecreaseShapeParameter => Scancode::Minus,
+            Self::QuaternionJuliaFromView => Scancode::P,
+            Self::FindMinibrot => Scancode::Z,
+            Self::FindMisiurewiczPoint => Scancode::X,
+            Self::EstimateExternalAngle => Scancode::W,
+            Self::RotateAnticlockwise => Scancode::Semicolon,
+            Self::RotateClockwise => Scancode::Apostrophe,
+            Self::DecreaseStretch => Scancode::Num4,
+            Self::IncreaseStretch => Scancode::Num5,
+            Self::DecreaseSkew => Scancode::Num6,
+            Self::IncreaseSkew => Scancode::Num7,
+            Self::ToggleJuliaPreview => Scancode::Grave,
+            Self::ToggleMinimap => Scancode::Y,
+            Self::ToggleOrbit => Scancode::Tab,
+            Self::ToggleRays => Scancode::R,
+            Self::ToggleSliceAnimation => Scancode::N,
+            Self::ToggleSoftShadows => Scancode::V,
+            Self::ToggleAmbientOcclusion => Scancode::B,
+            Self::EditFormula => Scancode::F,
+            Self::NextSupersampling => Scancode::S,
+            Self::ToggleAdaptiveSupersampling => Scancode::A,
+            Self::NextShading => Scancode::D,
+            Self::ToggleHistogramLock => Scancode::H,
+            Self::ToggleColourCycling => Scancode::C,
+            Self::FasterColourCycling => Scancode::RightBracket,
+            Self::SlowerColourCycling => Scancode::LeftBracket,
+            Self::ReverseColourCycling => Scancode::Backslash,
+            Self::ToggleDeviceColouring => Scancode::G,
+            Self::NextOrbitTrap => Scancode::T,
+            Self::LightAnticlockwise => Scancode::J,
+            Self::LightClockwise => Scancode::L,
+            Self::RaiseLight => Scancode::I,
+            Self::LowerLight => Scancode::K,
+            Self::MoveLightAway => Scancode::U,
+            Self::MoveLightCloser => Scancode::O,
+            Self::ToggleFullscreen => Scancode::F11,
+            Self::FlyUp => Scancode::E,
+            Self::FlyDown => Scancode::Q,
+        }
+    }
+}
+
+/// Which key every action is bound to. A key may be bound to several actions, which all happen
+/// when it is pressed, and an action to no key at all.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    keys: HashMap<Action, Scancode>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            keys: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_key()))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Binds `action` to `key`, or to nothing for `None`.
+    pub fn bind(&mut self, action: Action, key: Option<Scancode>) {
+        match key {
+            Some(key) => self.keys.insert(action, key),
+            None => self.keys.remove(&action),
+        };
+    }
+
+    /// Every action bound to `key`, in the order of `Action::ALL`.
+    pub fn actions(&self, key: Scancode) -> impl Iterator<Item = Action> + '_ {
+        Action::ALL
+            .into_iter()
+            .filter(move |action| self.keys.get(action) == Some(&key))
+    }
+
+    /// Whether the key bound to `action` is held down.
+    pub fn is_held(&self, action: Action, keyboard: &KeyboardState) -> bool {
+        self.keys
+            .get(&action)
+            .is_some_and(|&key| keyboard.is_scancode_pressed(key))
+    }
+}
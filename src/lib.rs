@@ -0,0 +1,6 @@
+//! Numerical tools behind the viewer that scripts can use without opening a window.
+
+pub mod double_double;
+pub mod external_ray;
+pub mod minibrot;
+pub mod misiurewicz;
@@ -0,0 +1,57 @@
+use std::num::NonZeroUsize;
+
+use sdl2::render::Texture;
+
+use crate::error::{InvalidBufferSize, WriteToTextureError};
+
+/// Colour of stable pixels, whose exponent is negative.
+const STABLE_COLOUR: [f64; 3] = [1.0, 0.8, 0.1];
+
+/// Colour of chaotic pixels, whose exponent is positive.
+const CHAOTIC_COLOUR: [f64; 3] = [0.1, 0.3, 1.0];
+
+/// The Lyapunov exponent of every pixel.
+pub struct LyapunovImage {
+    buffer: Vec<f32>,
+    width: NonZeroUsize,
+}
+
+impl LyapunovImage {
+    pub fn from_buffer(buffer: Vec<f32>, width: NonZeroUsize) -> Result<Self, InvalidBufferSize> {
+        if buffer.is_empty() || !buffer.len().is_multiple_of(width.get()) {
+            return Err(InvalidBufferSize);
+        }
+        Ok(Self { buffer, width })
+    }
+
+    /// Shades stable and chaotic pixels in separate colours, both fading to black as the exponent
+    /// approaches zero.
+    fn colour(exponent: f32) -> [u8; 3] {
+        let exponent = f64::from(exponent);
+        let (colour, intensity) = if exponent < 0.0 {
+            (STABLE_COLOUR, 1.0 - exponent.exp())
+        } else {
+            (CHAOTIC_COLOUR, 1.0 - (-exponent).exp())
+        };
+        // NaN only comes from diverging orbits, which are left black
+        if intensity.is_nan() {
+            return [0; 3];
+        }
+        colour.map(|channel| (channel * intensity * 255.0).round() as u8)
+    }
+
+    pub fn write_to_texture(&self, texture: &mut Texture) -> Result<(), WriteToTextureError> {
+        let query = texture.query();
+        let height = self.buffer.len() / self.width.get();
+        if self.width.get() != query.width as usize || height != query.height as usize {
+            return Err(WriteToTextureError::DimensionsDoNotMatch);
+        }
+        let texture_data = self
+            .buffer
+            .iter()
+            .flat_map(|&exponent| Self::colour(exponent))
+            .collect::<Vec<_>>();
+        texture.update(None, &texture_data, self.width.get() * 3)?;
+        Ok(())
+    }
+}
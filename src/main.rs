@@ -1,19 +1,55 @@
 #![feature(never_type)]
 
 use std::{
-    num::NonZeroUsize,
+    env,
+    f64::consts::{FRAC_PI_2, PI, TAU},
+    ffi::OsString,
+    num::{NonZeroU16, NonZeroUsize},
+    path::PathBuf,
     process::ExitCode,
     time::{Duration, Instant},
 };
 
-use error::{FatalError, IntegerOverflow, SdlError};
+use adaptive_iterations::AdaptiveIterations;
+use colouring::{Colouring, Shading};
+use comparison::{ComparisonView, Difference};
+use config::Config;
+use density_image::DensityImage;
+use error::{
+    ArgumentError, FatalError, FormulaError, IntegerOverflow, PaletteError, PolynomialError,
+    SdlError,
+};
+use formula::{CompiledFormula, Formula};
+use fraktaloj_gui::{
+    external_ray::{external_angle, trace_ray, ExternalAngle},
+    minibrot::Minibrot,
+    misiurewicz::MisiurewiczPoint,
+};
 use iteration_image::IterationImage;
+use keymap::Action;
+use lyapunov_image::LyapunovImage;
+use minimap::MiniMap;
+use newton_image::NewtonImage;
 use num::{Complex, Zero};
-use render::Renderer;
+use orbit::Orbit;
+use palette::PaletteFile;
+use render::{
+    density::{DensityParameters, DensityRenderer},
+    lyapunov::{LyapunovParameters, LyapunovRenderer},
+    newton::{parse_complex_list, NewtonRenderer, Polynomial},
+    orbit_trap::OrbitTrap,
+    raymarch::{Quaternion, RayMarchParameters, RayMarchRenderer, Shape},
+    supersampling::Supersampling,
+    transform::ViewTransform,
+    RenderOptions, Renderer,
+};
+use rgb_image::RgbImage;
 use sdl2::{
     event::Event,
     keyboard::Scancode,
+    mouse::MouseButton,
     pixels::{Color, PixelFormatEnum},
+    rect::{Point, Rect},
     rwops::RWops,
     ttf,
     video::FullscreenType,
@@ -21,23 +57,48 @@ use sdl2::{
 
 use crate::{
     error::OpenclError,
-    render::{cpu::ScalarCpuRenderer, opencl::OpenclRenderer},
+    render::{
+        cpu::ScalarCpuRenderer,
+        density::{cpu::CpuDensityRenderer, opencl::OpenclDensityRenderer},
+        lyapunov::{cpu::CpuLyapunovRenderer, opencl::OpenclLyapunovRenderer},
+        newton::{cpu::CpuNewtonRenderer, opencl::OpenclNewtonRenderer},
+        opencl::{DeviceImage, OpenclRenderer},
+        raymarch::{cpu::CpuRayMarchRenderer, opencl::OpenclRayMarchRenderer},
+    },
 };
 
+mod adaptive_iterations;
+mod benchmark;
+mod colouring;
+mod comparison;
+mod config;
+mod density_image;
 mod error;
+mod formula;
 mod iteration_image;
+mod keymap;
+mod lyapunov_image;
+mod minimap;
+mod newton_image;
+mod orbit;
+mod palette;
 mod render;
+mod rgb_image;
 
 static FONT: &[u8] = include_bytes!("font.ttf");
 
+/// Iteration step of the formula mode until another one is entered.
+const DEFAULT_FORMULA: &str = "z^3 + c";
+
 fn measure_render<T, R: Renderer<T>>(
     renderer: &mut R,
     center: Complex<T>,
     horizontal_radius: T,
     max_iterations: u32,
+    options: &RenderOptions,
 ) -> Result<(IterationImage, Duration), R::Error> {
     let instant = Instant::now();
-    let result = renderer.render(center, horizontal_radius, max_iterations);
+    let result = renderer.render(center, horizontal_radius, max_iterations, options);
     let duration = instant.elapsed();
     match result {
         Ok(image) => Ok((image, duration)),
@@ -45,6 +106,109 @@ fn measure_render<T, R: Renderer<T>>(
     }
 }
 
+fn measure_accumulate<R: DensityRenderer>(
+    renderer: &mut R,
+    center: Complex<f64>,
+    horizontal_radius: f64,
+    parameters: &DensityParameters,
+    samples: u64,
+) -> Result<(DensityImage, Duration), R::Error> {
+    let instant = Instant::now();
+    let image = renderer.accumulate(center, horizontal_radius, parameters, samples)?;
+    Ok((image, instant.elapsed()))
+}
+
+fn measure_newton<R: NewtonRenderer>(
+    renderer: &mut R,
+    center: Complex<f64>,
+    horizontal_radius: f64,
+    polynomial: &Polynomial,
+    max_iterations: u32,
+) -> Result<(NewtonImage, Duration), R::Error> {
+    let instant = Instant::now();
+    let image = renderer.render(center, horizontal_radius, polynomial, max_iterations)?;
+    Ok((image, instant.elapsed()))
+}
+
+fn measure_lyapunov<R: LyapunovRenderer>(
+    renderer: &mut R,
+    center: Complex<f64>,
+    horizontal_radius: f64,
+    parameters: &LyapunovParameters,
+    max_iterations: u32,
+) -> Result<(LyapunovImage, Duration), R::Error> {
+    let instant = Instant::now();
+    let image = renderer.render(center, horizontal_radius, parameters, max_iterations)?;
+    Ok((image, instant.elapsed()))
+}
+
+/// Point of the complex plane shown at window coordinates `(x, y)`, clamped to the window.
+fn window_to_complex(
+    (x, y): (i32, i32),
+    center: Complex<f64>,
+    radius: f64,
+    transform: &ViewTransform,
+    Dimensions { width, height }: Dimensions,
+) -> Complex<f64> {
+    let x = (x.max(0) as usize).min(width.get() - 1);
+    let y = (y.max(0) as usize).min(height.get() - 1);
+    transform.transform_point(
+        render::pixel_to_complex(y * width.get() + x, center, radius, width, height),
+        center,
+    )
+}
+
+/// Window coordinates showing `z`, kept within a band around the window so lines to faraway
+/// points still clip correctly instead of overflowing.
+fn complex_to_window(
+    z: Complex<f64>,
+    center: Complex<f64>,
+    radius: f64,
+    transform: &ViewTransform,
+    Dimensions { width, height }: Dimensions,
+) -> Point {
+    const LIMIT: f64 = 1e6;
+    let (width, height) = (width.get() as f64, height.get() as f64);
+    let vertical_radius = radius * height / width;
+    let offset = transform.inverse().unwrap_or_default().apply(z - center);
+    let x = (offset.re + radius) / (2.0 * radius) * width;
+    let y = (vertical_radius - offset.im) / (2.0 * vertical_radius) * height;
+    Point::new(x.clamp(-LIMIT, LIMIT) as i32, y.clamp(-LIMIT, LIMIT) as i32)
+}
+
+/// Angle of window coordinates `(x, y)` around the center of the view, anticlockwise from the
+/// right.
+fn view_angle((x, y): (i32, i32), Dimensions { width, height }: Dimensions) -> f64 {
+    let x = f64::from(x) - width.get() as f64 / 2.0;
+    let y = height.get() as f64 / 2.0 - f64::from(y);
+    y.atan2(x)
+}
+
+/// Where the mini-map sits in the window, in the bottom right corner.
+fn minimap_rect(view_dimensions: Dimensions, minimap_dimensions: Dimensions) -> Rect {
+    const MARGIN: i32 = 16;
+    let (width, height) = (
+        minimap_dimensions.width.get() as u32,
+        minimap_dimensions.height.get() as u32,
+    );
+    Rect::new(
+        view_dimensions.width.get() as i32 - width as i32 - MARGIN,
+        view_dimensions.height.get() as i32 - height as i32 - MARGIN,
+        width,
+        height,
+    )
+}
+
+fn measure_ray_march<R: RayMarchRenderer>(
+    renderer: &mut R,
+    parameters: &RayMarchParameters,
+) -> Result<(RgbImage, Duration), R::Error> {
+    let instant = Instant::now();
+    let image = renderer.render(parameters)?;
+    Ok((image, instant.elapsed()))
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
 enum RendererChoice {
     Cpu,
     Opencl,
@@ -73,18 +237,108 @@ impl TryFrom<(u32, u32)> for Dimensions {
     }
 }
 
-fn app() -> Result<(), FatalError> {
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum FractalMode {
+    Mandelbrot,
+    Buddhabrot,
+    Nebulabrot,
+    AntiBuddhabrot,
+    Newton,
+    Lyapunov,
+    /// Escape time of a user-defined iteration step.
+    Formula,
+    Mandelbulb,
+    Mandelbox,
+    QuaternionJulia,
+}
+
+impl FractalMode {
+    fn next(self) -> Self {
+        match self {
+            Self::Mandelbrot => Self::Buddhabrot,
+            Self::Buddhabrot => Self::Nebulabrot,
+            Self::Nebulabrot => Self::AntiBuddhabrot,
+            Self::AntiBuddhabrot => Self::Newton,
+            Self::Newton => Self::Lyapunov,
+            Self::Lyapunov => Self::Formula,
+            Self::Formula => Self::Mandelbulb,
+            Self::Mandelbulb => Self::Mandelbox,
+            Self::Mandelbox => Self::QuaternionJulia,
+            Self::QuaternionJulia => Self::Mandelbrot,
+        }
+    }
+
+    /// Center and radius of the view the Home key returns to. The Lyapunov fractal lives in the
+    /// plane of growth rates, where the interesting region lies between 2 and 4.
+    fn home(self) -> (Complex<f64>, f64) {
+        match self {
+            Self::Lyapunov => (Complex::new(3.0, 3.0), 1.0),
+            _ => (Complex::zero(), 2.0),
+        }
+    }
+
+    /// What to accumulate for the density modes, or `None` for escape time rendering.
+    fn density_parameters(self, max_iterations: u32) -> Option<DensityParameters> {
+        match self {
+            Self::Buddhabrot => Some(DensityParameters::buddhabrot(max_iterations)),
+            Self::Nebulabrot => Some(DensityParameters::nebulabrot(max_iterations)),
+            Self::AntiBuddhabrot => Some(DensityParameters::anti_buddhabrot(max_iterations)),
+            _ => None,
+        }
+    }
+
+    /// Index of the shape the 3D modes ray march, or `None` for the 2D modes.
+    fn shape_index(self) -> Option<usize> {
+        match self {
+            Self::Mandelbulb => Some(0),
+            Self::Mandelbox => Some(1),
+            Self::QuaternionJulia => Some(2),
+            _ => None,
+        }
+    }
+
+    fn is_three_dimensional(self) -> bool {
+        self.shape_index().is_some()
+    }
+
+    /// Whether the image shows escape times of z² + c or the formula.
+    fn is_escape_time(self) -> bool {
+        matches!(self, Self::Mandelbrot | Self::Formula)
+    }
+}
+
+/// Everything that determines the image a renderer produces.
+#[derive(PartialEq, Clone, Copy)]
+struct RenderParameters {
+    renderer_choice: RendererChoice,
+    center: Complex<f64>,
+    radius: f64,
+    max_iterations: u32,
+    options: RenderOptions,
+    dimensions: Dimensions,
+    device_colouring: bool,
+}
+
+enum RenderedImage {
+    Host(IterationImage),
+    /// Samples kept on the OpenCL device, which also colours them.
+    Device(DeviceImage),
+}
+
+/// Size of the HUD's text for a window `width` pixels wide, unless the configuration sets one.
+fn font_size(configured: Option<NonZeroU16>, width: NonZeroUsize) -> u16 {
+    configured.map_or((width.get() as f64 * 0.025) as u16, NonZeroU16::get)
+}
+
+fn app(arguments: Arguments, config: Config) -> Result<(), FatalError> {
     let sdl = sdl2::init().map_err(SdlError::from)?;
     let video = sdl.video().map_err(SdlError::from)?;
-    let mut window_dimensions = Dimensions::from((
-        NonZeroUsize::new(1280).unwrap(),
-        NonZeroUsize::new(720).unwrap(),
-    ));
+    let mut view_dimensions = config.window_dimensions;
     let window = video
         .window(
             "Fraktaloj GUI",
-            window_dimensions.width.get().try_into()?,
-            window_dimensions.height.get().try_into()?,
+            view_dimensions.width.get().try_into()?,
+            view_dimensions.height.get().try_into()?,
         )
         .resizable()
         .build()
@@ -95,6 +349,8 @@ fn app() -> Result<(), FatalError> {
         .build()
         .map_err(SdlError::from)?;
     let mut events = sdl.event_pump().map_err(SdlError::from)?;
+    // The main view covers the whole window unless the Julia preview takes its right half
+    let mut window_dimensions = view_dimensions;
 
     let mut fullscreen = false;
 
@@ -102,114 +358,501 @@ fn app() -> Result<(), FatalError> {
     let mut texture = texture_creator
         .create_texture_streaming(
             Some(PixelFormatEnum::RGB24),
-            window_dimensions.width.get().try_into()?,
-            window_dimensions.height.get().try_into()?,
+            view_dimensions.width.get().try_into()?,
+            view_dimensions.height.get().try_into()?,
+        )
+        .map_err(SdlError::from)?;
+    // The OpenCL half of the backend comparison
+    let mut comparison_texture = texture_creator
+        .create_texture_streaming(
+            Some(PixelFormatEnum::RGB24),
+            view_dimensions.width.get().try_into()?,
+            view_dimensions.height.get().try_into()?,
         )
         .map_err(SdlError::from)?;
+    let mut comparison_view = ComparisonView::Off;
+    // Both images of the last comparison with what they were rendered for
+    let mut comparison: Option<(
+        RenderParameters,
+        IterationImage,
+        IterationImage,
+        Difference,
+        Duration,
+    )> = None;
 
     let ttf_context = ttf::init().map_err(SdlError::from)?;
     let mut font = ttf_context
-        .load_font_from_rwops(RWops::from_bytes(FONT).map_err(SdlError::from)?, 32)
+        .load_font_from_rwops(
+            RWops::from_bytes(FONT).map_err(SdlError::from)?,
+            font_size(config.font_size, view_dimensions.width),
+        )
         .map_err(SdlError::from)?;
 
-    let mut cpu_renderer =
-        ScalarCpuRenderer::new(window_dimensions.width, window_dimensions.height)?;
+    let mut cpu_renderer = ScalarCpuRenderer::new(view_dimensions.width, view_dimensions.height)?;
+
+    let mut opencl_renderer = OpenclRenderer::new(view_dimensions.width, view_dimensions.height)?;
+
+    let mut cpu_density_renderer =
+        CpuDensityRenderer::new(view_dimensions.width, view_dimensions.height)?;
+
+    let mut opencl_density_renderer =
+        OpenclDensityRenderer::new(view_dimensions.width, view_dimensions.height)?;
+
+    let mut cpu_newton_renderer =
+        CpuNewtonRenderer::new(view_dimensions.width, view_dimensions.height)?;
+
+    let mut opencl_newton_renderer =
+        OpenclNewtonRenderer::new(view_dimensions.width, view_dimensions.height)?;
 
-    let mut opencl_renderer =
-        OpenclRenderer::new(window_dimensions.width, window_dimensions.height)?;
+    let mut cpu_lyapunov_renderer =
+        CpuLyapunovRenderer::new(view_dimensions.width, view_dimensions.height)?;
+
+    let mut opencl_lyapunov_renderer =
+        OpenclLyapunovRenderer::new(view_dimensions.width, view_dimensions.height)?;
+
+    let mut cpu_ray_march_renderer =
+        CpuRayMarchRenderer::new(view_dimensions.width, view_dimensions.height)?;
+
+    let mut opencl_ray_march_renderer =
+        OpenclRayMarchRenderer::new(view_dimensions.width, view_dimensions.height)?;
 
     let opencl_display_string = format!(
         "OpenCL: {}",
         opencl_renderer.device_name().map_err(OpenclError::from)?
     );
 
-    let mut renderer_choice = RendererChoice::Cpu;
-    let mut max_iterations = 256_u32;
+    let mut renderer_choice = config.backend;
+    let mut fractal_mode = FractalMode::Mandelbrot;
+    let mut formula = Formula::parse(DEFAULT_FORMULA).unwrap();
+    let mut formula_error: Option<FormulaError> = None;
+    if let Some(source) = &arguments.formula {
+        match Formula::parse(source) {
+            Ok(parsed) => formula = parsed,
+            Err(error) => formula_error = Some(error),
+        }
+    }
+    // The formula being typed, if any
+    let mut formula_input: Option<String> = None;
+    // What the escape time renderers currently iterate instead of z² + c
+    let mut applied_formula: Option<Formula> = None;
+    video.text_input().stop();
+    let mut shapes = [Shape::MANDELBULB, Shape::MANDELBOX, Shape::QUATERNION_JULIA];
+    // Outcome of the last minibrot, Misiurewicz point or external angle search
+    let mut analysis_status = String::new();
+    // External rays with the one through the point last under the cursor at the end
+    let mut ray_angles = arguments.rays.clone();
+    let mut show_rays = true;
+    // Window coordinates of the mouse
+    let mut cursor = (0, 0);
+    let mut show_orbit = false;
+    let (minimap_center, minimap_radius) = FractalMode::Mandelbrot.home();
+    let mut minimap = MiniMap::new(
+        Dimensions::try_from((MINIMAP_WIDTH, MINIMAP_HEIGHT))?,
+        minimap_center,
+        minimap_radius,
+    )?;
+    let mut minimap_texture = texture_creator
+        .create_texture_streaming(Some(PixelFormatEnum::RGB24), MINIMAP_WIDTH, MINIMAP_HEIGHT)
+        .map_err(SdlError::from)?;
+    let mut show_minimap = true;
+    let mut julia_preview = false;
+    let mut julia_c = Complex::zero();
+    let mut julia_dimensions = Dimensions::try_from((1, 1))?;
+    let mut julia_renderer =
+        ScalarCpuRenderer::new(julia_dimensions.width, julia_dimensions.height)?;
+    let mut julia_texture = texture_creator
+        .create_texture_streaming(Some(PixelFormatEnum::RGB24), 1, 1)
+        .map_err(SdlError::from)?;
+    // Options, iteration limit and image of the last preview, kept while the cursor is still
+    let mut julia_render: Option<(RenderOptions, u32, IterationImage)> = None;
+    // The formula step compiled for the orbit overlay, if the renderers iterate one
+    let mut orbit_formula: Option<CompiledFormula> = None;
+    let mut traced_rays = Vec::new();
+    // View, iteration limit and ray count the rays were traced for
+    let mut traced_view = None;
+    let (misiurewicz_preperiod, misiurewicz_period) =
+        arguments.misiurewicz.unwrap_or(DEFAULT_MISIUREWICZ);
+    // Phase of the quaternion Julia set's slice while it sweeps back and forth
+    let mut slice_animation: Option<f64> = None;
+    let mut camera = Shape::MANDELBULB.home();
+    let mut soft_shadows = true;
+    let mut ambient_occlusion = true;
+    // Rotation in radians, stretch of the real axis and skew of the escape time views
+    let mut view_rotation = 0.0_f64;
+    let mut view_stretch = 1.0_f64;
+    let mut view_skew = 0.0_f64;
+    // Angle of the cursor around the view's center while the right button turns the view
+    let mut rotation_drag: Option<f64> = None;
+    let mut max_iterations = config.max_iterations;
+    // Chooses the iteration limit in the escape time modes until it is set by hand
+    let mut adaptive_iterations = config.adaptive_iterations.then(AdaptiveIterations::default);
+    let mut render_options = RenderOptions::default();
+    let mut colouring = Colouring::default();
+    let mut orbit_trap = OrbitTrap::default();
+    let mut palette_file = arguments
+        .palette
+        .clone()
+        .or(config.palette)
+        .map(PaletteFile::new);
+    let keymap = config.keymap;
+    let mut palette_error: Option<PaletteError> = None;
+    let mut colour_cycling = false;
+    let mut colour_cycling_speed = 0.25;
+    let mut device_colouring = false;
+    let mut last_render: Option<(RenderParameters, RenderedImage, Duration)> = None;
+    let mut last_frame = Instant::now();
 
     const ZOOM_MULTIPLIER: f64 = 1.25;
     const ZOOM_MULTIPLIER_INV: f64 = 1.0 / ZOOM_MULTIPLIER;
+    const LIGHT_ANGLE_STEP: f64 = PI / 12.0;
+    const ROTATION_STEP: f64 = PI / 36.0;
+    const STRETCH_MULTIPLIER: f64 = 1.05;
+    const SKEW_STEP: f64 = 0.05;
+    const LIGHT_HEIGHT_MULTIPLIER: f64 = 1.25;
+    const COLOUR_CYCLING_SPEED_MULTIPLIER: f64 = 1.5;
+    /// Orbits sampled per frame by the density modes, which refine their image progressively.
+    const DENSITY_SAMPLES_PER_FRAME: u64 = 1 << 18;
+    /// Fraction of the distance to the surface the 3D camera covers per second.
+    const FLY_SPEED: f64 = 0.5;
+    const MIN_FLY_SPEED: f64 = 1e-4;
+    /// Radians per second the 3D camera turns by.
+    const TURN_SPEED: f64 = 1.0;
+    /// Side in pixels of the squares marking orbit points.
+    const ORBIT_MARKER_SIZE: u32 = 5;
+    /// How many times coarser than the window the Julia preview is rendered.
+    const JULIA_PREVIEW_DOWNSCALE: u32 = 2;
+    const MINIMAP_WIDTH: u32 = 240;
+    const MINIMAP_HEIGHT: u32 = 180;
+    /// Half the length of the crosshair marking views too small to outline on the mini-map.
+    const MINIMAP_CROSSHAIR_SIZE: i32 = 6;
+    /// Distance from the center, in view radii, beyond which rays are not drawn.
+    const RAY_CLIP_RADIUS: f64 = 4.0;
+    /// Preperiod and period of the Misiurewicz point at -2.
+    const DEFAULT_MISIUREWICZ: (u32, u32) = (2, 1);
+    /// Radians per second the animated slice's phase advances by.
+    const SLICE_SPEED: f64 = 0.5;
+    /// Furthest the animated slice strays from zero along the fourth dimension.
+    const SLICE_AMPLITUDE: f64 = 1.0;
 
-    let mut center = Complex::zero();
-    let mut radius = 2.0;
+    let (mut center, mut radius) = FractalMode::Mandelbrot.home();
 
     'main_loop: loop {
         for event in events.poll_iter() {
+            // While typing a formula, keys edit it instead of controlling the view
+            if let Some(input) = &mut formula_input {
+                match event {
+                    Event::Quit { .. } => break 'main_loop,
+                    Event::TextInput { text, .. } => input.push_str(&text),
+                    Event::KeyDown {
+                        scancode: Some(Scancode::Backspace),
+                        ..
+                    } => {
+                        input.pop();
+                    }
+                    Event::KeyDown {
+                        scancode: Some(Scancode::Return | Scancode::KpEnter),
+                        ..
+                    } => match Formula::parse(input) {
+                        Ok(parsed) => {
+                            formula = parsed;
+                            formula_error = None;
+                            formula_input = None;
+                            fractal_mode = FractalMode::Formula;
+                            video.text_input().stop();
+                        }
+                        // Left open so the mistake can be fixed
+                        Err(error) => formula_error = Some(error),
+                    },
+                    Event::KeyDown {
+                        scancode: Some(Scancode::Escape),
+                        ..
+                    } => {
+                        formula_error = None;
+                        formula_input = None;
+                        video.text_input().stop();
+                    }
+                    _ => (),
+                }
+                continue;
+            }
+            // Panning follows the view's axes wherever the transform turned them
+            let screen_transform = if fractal_mode.is_escape_time() {
+                ViewTransform::new(view_rotation, view_stretch, view_skew)
+            } else {
+                ViewTransform::default()
+            };
             match event {
                 Event::Quit { .. } => break 'main_loop,
                 Event::KeyDown {
-                    scancode: Some(Scancode::Up),
-                    ..
-                } => center.im += 0.1 * radius,
-                Event::KeyDown {
-                    scancode: Some(Scancode::Left),
-                    ..
-                } => center.re -= 0.1 * radius,
-                Event::KeyDown {
-                    scancode: Some(Scancode::Down),
-                    ..
-                } => center.im -= 0.1 * radius,
-                Event::KeyDown {
-                    scancode: Some(Scancode::Right),
-                    ..
-                } => center.re += 0.1 * radius,
-                Event::KeyDown {
-                    scancode: Some(Scancode::PageUp),
-                    ..
-                } => radius *= ZOOM_MULTIPLIER_INV,
-                Event::KeyDown {
-                    scancode: Some(Scancode::PageDown),
-                    ..
-                } => radius *= ZOOM_MULTIPLIER,
-                Event::KeyDown {
-                    scancode: Some(Scancode::Home),
+                    scancode: Some(scancode),
                     ..
                 } => {
-                    center = Complex::zero();
-                    radius = 2.0;
+                    for action in keymap.actions(scancode) {
+                        match action {
+                            Action::PanUp if !fractal_mode.is_three_dimensional() => {
+                                center += screen_transform.apply(Complex::new(0.0, 0.1 * radius))
+                            }
+                            Action::PanLeft if !fractal_mode.is_three_dimensional() => {
+                                center += screen_transform.apply(Complex::new(-0.1 * radius, 0.0))
+                            }
+                            Action::PanDown if !fractal_mode.is_three_dimensional() => {
+                                center += screen_transform.apply(Complex::new(0.0, -0.1 * radius))
+                            }
+                            Action::PanRight if !fractal_mode.is_three_dimensional() => {
+                                center += screen_transform.apply(Complex::new(0.1 * radius, 0.0))
+                            }
+                            Action::ZoomIn if !fractal_mode.is_three_dimensional() => {
+                                radius *= ZOOM_MULTIPLIER_INV
+                            }
+                            Action::ZoomOut if !fractal_mode.is_three_dimensional() => {
+                                radius *= ZOOM_MULTIPLIER
+                            }
+                            Action::Home => match fractal_mode.shape_index() {
+                                Some(index) => camera = shapes[index].home(),
+                                None => {
+                                    (center, radius) = fractal_mode.home();
+                                    (view_rotation, view_stretch, view_skew) = (0.0, 1.0, 0.0);
+                                }
+                            },
+                            Action::UseCpu => renderer_choice = RendererChoice::Cpu,
+                            Action::UseOpencl => renderer_choice = RendererChoice::Opencl,
+                            Action::NextComparisonView => comparison_view = comparison_view.next(),
+                            Action::DoubleIterations => {
+                                adaptive_iterations = None;
+                                if let Some(new_value) = max_iterations.checked_mul(2) {
+                                    max_iterations = new_value;
+                                }
+                            }
+                            Action::HalveIterations => {
+                                adaptive_iterations = None;
+                                if let Some(new_value @ 1..) = max_iterations.checked_div(2) {
+                                    max_iterations = new_value;
+                                }
+                            }
+                            Action::ToggleAdaptiveIterations => {
+                                adaptive_iterations = match adaptive_iterations {
+                                    Some(_) => None,
+                                    None => Some(AdaptiveIterations::default()),
+                                }
+                            }
+                            Action::NextFractal => {
+                                let next = fractal_mode.next();
+                                // Views of different planes mean nothing to each other
+                                if next.home() != fractal_mode.home() {
+                                    (center, radius) = next.home();
+                                }
+                                if let Some(index) = next.shape_index() {
+                                    camera = shapes[index].home();
+                                }
+                                fractal_mode = next;
+                            }
+                            Action::IncreaseShapeParameter => {
+                                if let Some(index) = fractal_mode.shape_index() {
+                                    shapes[index].adjust(1);
+                                }
+                            }
+                            Action::DecreaseShapeParameter => {
+                                if let Some(index) = fractal_mode.shape_index() {
+                                    shapes[index].adjust(-1);
+                                }
+                            }
+                            Action::QuaternionJuliaFromView
+                                if fractal_mode == FractalMode::Mandelbrot =>
+                            {
+                                // The view's center becomes the constant of the Julia set
+                                if let Shape::QuaternionJulia { constant, .. } = &mut shapes[2] {
+                                    *constant = Quaternion::from(center);
+                                }
+                                fractal_mode = FractalMode::QuaternionJulia;
+                                camera = shapes[2].home();
+                            }
+                            Action::FindMinibrot if fractal_mode == FractalMode::Mandelbrot => {
+                                // The box must lie within the view, whose vertical radius is the smaller one
+                                let box_radius = radius * view_dimensions.height.get() as f64
+                                    / view_dimensions.width.get() as f64;
+                                analysis_status =
+                                    match Minibrot::find(center, box_radius, max_iterations) {
+                                        Some(minibrot) => {
+                                            center = minibrot.center();
+                                            radius = minibrot.view_radius();
+                                            format!("\nMinibrot: {minibrot}")
+                                        }
+                                        None => "\nMinibrot: none found in view".to_string(),
+                                    };
+                            }
+                            Action::FindMisiurewiczPoint
+                                if fractal_mode == FractalMode::Mandelbrot =>
+                            {
+                                analysis_status = match MisiurewiczPoint::find(
+                                center,
+                                misiurewicz_preperiod,
+                                misiurewicz_period,
+                            ) {
+                                Some(point) => {
+                                    center = point.center();
+                                    format!("\nMisiurewicz point: {point}")
+                                }
+                                None => format!(
+                                    "\nMisiurewicz point: no M{misiurewicz_preperiod},{misiurewicz_period} found near the center"
+                                ),
+                            };
+                            }
+                            Action::EstimateExternalAngle
+                                if fractal_mode == FractalMode::Mandelbrot =>
+                            {
+                                let point = window_to_complex(
+                                    cursor,
+                                    center,
+                                    radius,
+                                    &render_options.transform,
+                                    view_dimensions,
+                                );
+                                ray_angles.truncate(arguments.rays.len());
+                                analysis_status = match external_angle(point, max_iterations) {
+                                Some(angle) => {
+                                    let status = format!(
+                                        "\nExternal angle under cursor: about {:.8} turns ({} binary digits)",
+                                        angle.turns(),
+                                        angle.to_string().len() - 2
+                                    );
+                                    ray_angles.push(angle);
+                                    status
+                                }
+                                None => "\nExternal angle under cursor: none, the point did not escape"
+                                    .to_string(),
+                            };
+                            }
+                            Action::RotateAnticlockwise if fractal_mode.is_escape_time() => {
+                                view_rotation = (view_rotation + ROTATION_STEP).rem_euclid(TAU)
+                            }
+                            Action::RotateClockwise if fractal_mode.is_escape_time() => {
+                                view_rotation = (view_rotation - ROTATION_STEP).rem_euclid(TAU)
+                            }
+                            Action::DecreaseStretch if fractal_mode.is_escape_time() => {
+                                view_stretch /= STRETCH_MULTIPLIER
+                            }
+                            Action::IncreaseStretch if fractal_mode.is_escape_time() => {
+                                view_stretch *= STRETCH_MULTIPLIER
+                            }
+                            Action::DecreaseSkew if fractal_mode.is_escape_time() => {
+                                view_skew -= SKEW_STEP
+                            }
+                            Action::IncreaseSkew if fractal_mode.is_escape_time() => {
+                                view_skew += SKEW_STEP
+                            }
+                            Action::ToggleJuliaPreview => julia_preview = !julia_preview,
+                            Action::ToggleMinimap => show_minimap = !show_minimap,
+                            Action::ToggleOrbit => show_orbit = !show_orbit,
+                            Action::ToggleRays => show_rays = !show_rays,
+                            Action::ToggleSliceAnimation => {
+                                slice_animation = match slice_animation {
+                                    Some(_) => None,
+                                    None => Some(0.0),
+                                }
+                            }
+                            Action::ToggleSoftShadows => soft_shadows = !soft_shadows,
+                            Action::ToggleAmbientOcclusion => {
+                                ambient_occlusion = !ambient_occlusion
+                            }
+                            Action::EditFormula => {
+                                formula_input = Some(formula.source().to_string());
+                                video.text_input().start();
+                            }
+                            Action::NextSupersampling => {
+                                render_options.supersampling.next_pattern()
+                            }
+                            Action::ToggleAdaptiveSupersampling => {
+                                render_options.supersampling.adaptive =
+                                    !render_options.supersampling.adaptive
+                            }
+                            Action::NextShading => colouring.shading = colouring.shading.next(),
+                            Action::ToggleHistogramLock => {
+                                colouring.histogram_locked = !colouring.histogram_locked
+                            }
+                            Action::ToggleColourCycling => colour_cycling = !colour_cycling,
+                            Action::FasterColourCycling => {
+                                colour_cycling_speed *= COLOUR_CYCLING_SPEED_MULTIPLIER
+                            }
+                            Action::SlowerColourCycling => {
+                                colour_cycling_speed /= COLOUR_CYCLING_SPEED_MULTIPLIER
+                            }
+                            Action::ReverseColourCycling => {
+                                colour_cycling_speed = -colour_cycling_speed
+                            }
+                            Action::ToggleDeviceColouring => device_colouring = !device_colouring,
+                            Action::NextOrbitTrap => orbit_trap = orbit_trap.next(),
+                            Action::LightAnticlockwise => {
+                                colouring.light.azimuth -= LIGHT_ANGLE_STEP
+                            }
+                            Action::LightClockwise => colouring.light.azimuth += LIGHT_ANGLE_STEP,
+                            Action::RaiseLight => {
+                                colouring.light.elevation =
+                                    (colouring.light.elevation + LIGHT_ANGLE_STEP).min(FRAC_PI_2)
+                            }
+                            Action::LowerLight => {
+                                colouring.light.elevation =
+                                    (colouring.light.elevation - LIGHT_ANGLE_STEP).max(0.0)
+                            }
+                            Action::MoveLightAway => {
+                                colouring.light.height *= LIGHT_HEIGHT_MULTIPLIER
+                            }
+                            Action::MoveLightCloser => {
+                                colouring.light.height /= LIGHT_HEIGHT_MULTIPLIER
+                            }
+                            Action::ToggleFullscreen => {
+                                fullscreen = !fullscreen;
+                                canvas
+                                    .window_mut()
+                                    .set_fullscreen(if fullscreen {
+                                        FullscreenType::True
+                                    } else {
+                                        FullscreenType::Off
+                                    })
+                                    .map_err(SdlError::from)?;
+                                if fullscreen {
+                                    let display_mode =
+                                        video.desktop_display_mode(0).map_err(SdlError::from)?;
+                                    canvas
+                                        .window_mut()
+                                        .set_size(display_mode.w as u32, display_mode.h as u32)
+                                        .map_err(SdlError::from)?;
+                                }
+                            }
+
+                            _ => (),
+                        }
+                    }
                 }
-                Event::KeyDown {
-                    scancode: Some(Scancode::Num1),
-                    ..
-                } => renderer_choice = RendererChoice::Cpu,
-                Event::KeyDown {
-                    scancode: Some(Scancode::Num2),
-                    ..
-                } => renderer_choice = RendererChoice::Opencl,
-                Event::KeyDown {
-                    scancode: Some(Scancode::Period),
-                    ..
-                } => {
-                    if let Some(new_value) = max_iterations.checked_mul(2) {
-                        max_iterations = new_value;
+                Event::MouseMotion { x, y, .. } => {
+                    cursor = (x, y);
+                    if let Some(drag_angle) = &mut rotation_drag {
+                        // The view turns so the point under the cursor stays under it
+                        let angle = view_angle(cursor, view_dimensions);
+                        view_rotation = (view_rotation - (angle - *drag_angle)).rem_euclid(TAU);
+                        *drag_angle = angle;
                     }
                 }
-                Event::KeyDown {
-                    scancode: Some(Scancode::Comma),
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Right,
+                    x,
+                    y,
                     ..
-                } => {
-                    if let Some(new_value @ 1..) = max_iterations.checked_div(2) {
-                        max_iterations = new_value;
-                    }
+                } if fractal_mode.is_escape_time() => {
+                    rotation_drag = Some(view_angle((x, y), view_dimensions))
                 }
-                Event::KeyDown {
-                    scancode: Some(Scancode::F11),
+                Event::MouseButtonUp {
+                    mouse_btn: MouseButton::Right,
                     ..
-                } => {
-                    fullscreen = !fullscreen;
-                    canvas
-                        .window_mut()
-                        .set_fullscreen(if fullscreen {
-                            FullscreenType::True
-                        } else {
-                            FullscreenType::Off
-                        })
-                        .map_err(SdlError::from)?;
-                    if fullscreen {
-                        let display_mode = video.desktop_display_mode(0).map_err(SdlError::from)?;
-                        canvas
-                            .window_mut()
-                            .set_size(display_mode.w as u32, display_mode.h as u32)
-                            .map_err(SdlError::from)?;
+                } => rotation_drag = None,
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } if show_minimap && fractal_mode.is_escape_time() => {
+                    let inset = minimap_rect(view_dimensions, minimap.dimensions());
+                    if inset.contains_point((x, y)) {
+                        center = minimap.point_at(x - inset.x(), y - inset.y());
                     }
                 }
                 _ => (),
@@ -217,47 +860,634 @@ fn app() -> Result<(), FatalError> {
         }
 
         let current_window_dimensions = Dimensions::try_from(canvas.window().size())?;
-        if current_window_dimensions != window_dimensions {
-            cpu_renderer.resize(current_window_dimensions)?;
-            opencl_renderer.resize(current_window_dimensions)?;
+        let split = julia_preview && fractal_mode == FractalMode::Mandelbrot;
+        let current_view_dimensions = if split {
+            Dimensions {
+                width: NonZeroUsize::new(current_window_dimensions.width.get() / 2)
+                    .unwrap_or(NonZeroUsize::MIN),
+                ..current_window_dimensions
+            }
+        } else {
+            current_window_dimensions
+        };
+        if current_view_dimensions != view_dimensions {
+            cpu_renderer.resize(current_view_dimensions)?;
+            opencl_renderer.resize(current_view_dimensions)?;
+            cpu_density_renderer.resize(current_view_dimensions)?;
+            opencl_density_renderer.resize(current_view_dimensions)?;
+            cpu_newton_renderer.resize(current_view_dimensions)?;
+            opencl_newton_renderer.resize(current_view_dimensions)?;
+            cpu_lyapunov_renderer.resize(current_view_dimensions)?;
+            opencl_lyapunov_renderer.resize(current_view_dimensions)?;
+            cpu_ray_march_renderer.resize(current_view_dimensions)?;
+            opencl_ray_march_renderer.resize(current_view_dimensions)?;
             texture = texture_creator
                 .create_texture_streaming(
                     Some(PixelFormatEnum::RGB24),
-                    current_window_dimensions.width.get().try_into()?,
-                    current_window_dimensions.height.get().try_into()?,
+                    current_view_dimensions.width.get().try_into()?,
+                    current_view_dimensions.height.get().try_into()?,
+                )
+                .map_err(SdlError::from)?;
+            comparison_texture = texture_creator
+                .create_texture_streaming(
+                    Some(PixelFormatEnum::RGB24),
+                    current_view_dimensions.width.get().try_into()?,
+                    current_view_dimensions.height.get().try_into()?,
+                )
+                .map_err(SdlError::from)?;
+            view_dimensions = current_view_dimensions;
+        }
+        // The preview pane is whatever the main view leaves of the window, at lower resolution
+        let julia_pane = Rect::new(
+            view_dimensions.width.get() as i32,
+            0,
+            (current_window_dimensions.width.get() - view_dimensions.width.get()) as u32,
+            current_window_dimensions.height.get() as u32,
+        );
+        let current_julia_dimensions = Dimensions::try_from((
+            (julia_pane.width() / JULIA_PREVIEW_DOWNSCALE).max(1),
+            (julia_pane.height() / JULIA_PREVIEW_DOWNSCALE).max(1),
+        ))?;
+        if split && current_julia_dimensions != julia_dimensions {
+            julia_renderer.resize(current_julia_dimensions)?;
+            julia_texture = texture_creator
+                .create_texture_streaming(
+                    Some(PixelFormatEnum::RGB24),
+                    current_julia_dimensions.width.get().try_into()?,
+                    current_julia_dimensions.height.get().try_into()?,
                 )
                 .map_err(SdlError::from)?;
+            julia_dimensions = current_julia_dimensions;
+            julia_render = None;
+        }
+        if current_window_dimensions != window_dimensions {
             font = ttf_context
                 .load_font_from_rwops(
                     RWops::from_bytes(FONT).map_err(SdlError::from)?,
-                    (current_window_dimensions.width.get() as f64 * 0.025) as u16,
+                    font_size(config.font_size, current_window_dimensions.width),
                 )
                 .map_err(SdlError::from)?;
             window_dimensions = current_window_dimensions;
         }
 
+        if let Some(result) = palette_file.as_mut().and_then(PaletteFile::poll) {
+            match result {
+                Ok(palette) => {
+                    colouring.palette = palette;
+                    palette_error = None;
+                }
+                Err(error) => palette_error = Some(error),
+            }
+        }
+
+        // Only gather the per-sample data the current shading needs
+        render_options.distance_estimation = colouring.shading == Shading::DistanceEstimate;
+        render_options.normals = colouring.shading == Shading::Lighting;
+        render_options.orbit_trap = (colouring.shading == Shading::OrbitTrap).then_some(orbit_trap);
+
+        let frame_time = last_frame.elapsed();
+        last_frame = Instant::now();
+        if colour_cycling {
+            colouring.cycle_offset = (colouring.cycle_offset
+                + colour_cycling_speed * frame_time.as_secs_f64())
+            .rem_euclid(1.0);
+        }
+
+        if let Some(index) = fractal_mode.shape_index() {
+            let keyboard = events.keyboard_state();
+            let axis = |positive, negative| {
+                f64::from(
+                    i8::from(keymap.is_held(positive, &keyboard))
+                        - i8::from(keymap.is_held(negative, &keyboard)),
+                )
+            };
+            let seconds = frame_time.as_secs_f64();
+            // Slowing down near the surface keeps the camera from flying through it
+            let speed = shapes[index]
+                .distance_estimate(camera.position)
+                .max(MIN_FLY_SPEED)
+                * FLY_SPEED
+                * seconds;
+            camera.fly(
+                axis(Action::FlyForward, Action::FlyBackward) * speed,
+                0.0,
+                axis(Action::FlyUp, Action::FlyDown) * speed,
+            );
+            camera.turn(
+                axis(Action::TurnRight, Action::TurnLeft) * TURN_SPEED * seconds,
+                axis(Action::TurnUp, Action::TurnDown) * TURN_SPEED * seconds,
+            );
+            if let (Some(phase), Shape::QuaternionJulia { slice, .. }) =
+                (&mut slice_animation, &mut shapes[index])
+            {
+                *phase += SLICE_SPEED * seconds;
+                *slice = SLICE_AMPLITUDE * phase.sin();
+            }
+        }
+
+        let wanted_formula = (fractal_mode == FractalMode::Formula).then(|| formula.clone());
+        if wanted_formula != applied_formula {
+            cpu_renderer.set_formula(wanted_formula.as_ref());
+            opencl_renderer.set_formula(wanted_formula.as_ref())?;
+            orbit_formula = wanted_formula.as_ref().map(Formula::compile);
+            applied_formula = wanted_formula;
+            // The image was rendered with the previous step
+            last_render = None;
+        }
+
         canvas.clear();
-        let (image, duration) = match renderer_choice {
-            RendererChoice::Cpu => {
-                measure_render(&mut cpu_renderer, center, radius, max_iterations).unwrap()
+        render_options.transform = ViewTransform::new(view_rotation, view_stretch, view_skew);
+        if let Some(adaptive_iterations) = &adaptive_iterations {
+            if fractal_mode.is_escape_time() {
+                max_iterations = adaptive_iterations.max_iterations(radius);
             }
-            RendererChoice::Opencl => {
-                measure_render(&mut opencl_renderer, center, radius, max_iterations)
-                    .map_err(OpenclError::from)?
+        }
+        // While cycling colours the last image is recoloured unless something it depends on changed
+        let render_parameters = RenderParameters {
+            renderer_choice,
+            center,
+            radius,
+            max_iterations,
+            options: render_options,
+            dimensions: view_dimensions,
+            device_colouring: device_colouring
+                && renderer_choice == RendererChoice::Opencl
+                && OpenclRenderer::can_colour(&render_options, &colouring),
+        };
+        let (duration, fractal_description) = if let Some(index) = fractal_mode.shape_index() {
+            let parameters = RayMarchParameters {
+                shape: shapes[index],
+                camera,
+                soft_shadows,
+                ambient_occlusion,
+            };
+            let (image, duration) = match renderer_choice {
+                RendererChoice::Cpu => {
+                    measure_ray_march(&mut cpu_ray_march_renderer, &parameters).unwrap()
+                }
+                RendererChoice::Opencl => {
+                    measure_ray_march(&mut opencl_ray_march_renderer, &parameters)
+                        .map_err(OpenclError::from)?
+                }
+            };
+            image
+                .write_to_texture(&mut texture)
+                .map_err(SdlError::from)?;
+            (
+                duration,
+                format!(
+                    "{}, soft shadows {}, ambient occlusion {}",
+                    shapes[index],
+                    if soft_shadows { "on" } else { "off" },
+                    if ambient_occlusion { "on" } else { "off" }
+                ),
+            )
+        } else if fractal_mode == FractalMode::Newton {
+            let (image, duration) = match renderer_choice {
+                RendererChoice::Cpu => measure_newton(
+                    &mut cpu_newton_renderer,
+                    center,
+                    radius,
+                    &arguments.polynomial,
+                    max_iterations,
+                )
+                .unwrap(),
+                RendererChoice::Opencl => measure_newton(
+                    &mut opencl_newton_renderer,
+                    center,
+                    radius,
+                    &arguments.polynomial,
+                    max_iterations,
+                )
+                .map_err(OpenclError::from)?,
+            };
+            image
+                .write_to_texture(&mut texture)
+                .map_err(SdlError::from)?;
+            (duration, format!("Newton, {}", arguments.polynomial))
+        } else if fractal_mode == FractalMode::Lyapunov {
+            let (image, duration) = match renderer_choice {
+                RendererChoice::Cpu => measure_lyapunov(
+                    &mut cpu_lyapunov_renderer,
+                    center,
+                    radius,
+                    &arguments.lyapunov,
+                    max_iterations,
+                )
+                .unwrap(),
+                RendererChoice::Opencl => measure_lyapunov(
+                    &mut opencl_lyapunov_renderer,
+                    center,
+                    radius,
+                    &arguments.lyapunov,
+                    max_iterations,
+                )
+                .map_err(OpenclError::from)?,
+            };
+            image
+                .write_to_texture(&mut texture)
+                .map_err(SdlError::from)?;
+            (duration, format!("Lyapunov, {}", arguments.lyapunov))
+        } else if let Some(parameters) = fractal_mode.density_parameters(max_iterations) {
+            let (image, duration) = match renderer_choice {
+                RendererChoice::Cpu => measure_accumulate(
+                    &mut cpu_density_renderer,
+                    center,
+                    radius,
+                    &parameters,
+                    DENSITY_SAMPLES_PER_FRAME,
+                )
+                .unwrap(),
+                RendererChoice::Opencl => measure_accumulate(
+                    &mut opencl_density_renderer,
+                    center,
+                    radius,
+                    &parameters,
+                    DENSITY_SAMPLES_PER_FRAME,
+                )
+                .map_err(OpenclError::from)?,
+            };
+            image
+                .write_to_texture(&mut texture)
+                .map_err(SdlError::from)?;
+            (
+                duration,
+                format!("{parameters}, {} samples", image.samples()),
+            )
+        } else if comparison_view != ComparisonView::Off && fractal_mode.is_escape_time() {
+            let images_are_new = comparison
+                .as_ref()
+                .is_none_or(|(parameters, ..)| *parameters != render_parameters);
+            if images_are_new {
+                let (cpu_image, cpu_duration) = measure_render(
+                    &mut cpu_renderer,
+                    center,
+                    radius,
+                    max_iterations,
+                    &render_options,
+                )
+                .unwrap();
+                let (opencl_image, opencl_duration) = measure_render(
+                    &mut opencl_renderer,
+                    center,
+                    radius,
+                    max_iterations,
+                    &render_options,
+                )
+                .map_err(OpenclError::from)?;
+                let difference = Difference::new(&cpu_image, &opencl_image);
+                if let Some(adaptive_iterations) = &mut adaptive_iterations {
+                    adaptive_iterations.update(&cpu_image);
+                }
+                comparison = Some((
+                    render_parameters,
+                    cpu_image,
+                    opencl_image,
+                    difference,
+                    cpu_duration + opencl_duration,
+                ));
             }
+            // Rendered above if there was no previous comparison
+            let (_, cpu_image, opencl_image, difference, duration) = comparison.as_ref().unwrap();
+            let view_description = if comparison_view == ComparisonView::Split {
+                colouring.prepare(cpu_image, images_are_new);
+                cpu_image
+                    .write_to_texture(&mut texture, &colouring)
+                    .map_err(SdlError::from)?;
+                opencl_image
+                    .write_to_texture(&mut comparison_texture, &colouring)
+                    .map_err(SdlError::from)?;
+                "CPU left, OpenCL right"
+            } else {
+                difference
+                    .heatmap()
+                    .write_to_texture(&mut texture)
+                    .map_err(SdlError::from)?;
+                "iteration difference heatmap"
+            };
+            (
+                *duration,
+                format!("Backend comparison, {view_description}, {difference}"),
+            )
+        } else {
+            // Other renders on the OpenCL renderer replace the samples of an image left there
+            let image_is_new = !colour_cycling
+                || last_render.as_ref().is_none_or(|(parameters, image, _)| {
+                    *parameters != render_parameters
+                        || matches!(image, RenderedImage::Device(image)
+                                if !opencl_renderer.holds(image))
+                });
+            if image_is_new {
+                let (image, duration) = match renderer_choice {
+                    _ if render_parameters.device_colouring => {
+                        let instant = Instant::now();
+                        let image = opencl_renderer
+                            .render_on_device(center, radius, max_iterations, &render_options)
+                            .map_err(OpenclError::from)?;
+                        (RenderedImage::Device(image), instant.elapsed())
+                    }
+                    RendererChoice::Cpu => {
+                        let (image, duration) = measure_render(
+                            &mut cpu_renderer,
+                            center,
+                            radius,
+                            max_iterations,
+                            &render_options,
+                        )
+                        .unwrap();
+                        (RenderedImage::Host(image), duration)
+                    }
+                    RendererChoice::Opencl => {
+                        let (image, duration) = measure_render(
+                            &mut opencl_renderer,
+                            center,
+                            radius,
+                            max_iterations,
+                            &render_options,
+                        )
+                        .map_err(OpenclError::from)?;
+                        (RenderedImage::Host(image), duration)
+                    }
+                };
+                last_render = Some((render_parameters, image, duration));
+            }
+            // Rendered above if there was no previous image
+            let (_, image, duration) = last_render.as_ref().unwrap();
+            match image {
+                RenderedImage::Host(image) => {
+                    if let Some(adaptive_iterations) = &mut adaptive_iterations {
+                        if image_is_new {
+                            adaptive_iterations.update(image);
+                        }
+                    }
+                    colouring.prepare(image, image_is_new);
+                    image
+                        .write_to_texture(&mut texture, &colouring)
+                        .map_err(SdlError::from)?;
+                }
+                RenderedImage::Device(image) => {
+                    if let Some(adaptive_iterations) = &mut adaptive_iterations {
+                        if image_is_new {
+                            // Only the iteration counts leave the device, not the whole image
+                            let iterations = opencl_renderer.read_iterations(image)?;
+                            adaptive_iterations.update(&iterations);
+                        }
+                    }
+                    opencl_renderer
+                        .colour(image, &colouring)?
+                        .write_to_texture(&mut texture)
+                        .map_err(SdlError::from)?
+                }
+            }
+            let description = match &applied_formula {
+                Some(formula) => format!("Formula, z = {}", formula.source()),
+                None => "Mandelbrot".to_string(),
+            };
+            (*duration, description)
         };
-        image
-            .write_to_texture(&mut texture)
+        let view_rect = Rect::new(
+            0,
+            0,
+            view_dimensions.width.get() as u32,
+            view_dimensions.height.get() as u32,
+        );
+        canvas
+            .copy(&texture, None, view_rect)
             .map_err(SdlError::from)?;
-        canvas.copy(&texture, None, None).map_err(SdlError::from)?;
+        if comparison_view == ComparisonView::Split && fractal_mode.is_escape_time() {
+            // Both images cover the whole view, so their halves line up exactly
+            let half_width = view_rect.width() / 2;
+            let right_half = Rect::new(
+                half_width as i32,
+                0,
+                view_rect.width() - half_width,
+                view_rect.height(),
+            );
+            canvas
+                .copy(&comparison_texture, right_half, right_half)
+                .map_err(SdlError::from)?;
+            canvas.set_draw_color(Color::WHITE);
+            canvas
+                .draw_line(right_half.top_left(), right_half.bottom_left())
+                .map_err(SdlError::from)?;
+        }
+        if split {
+            // Follows the cursor only while it is over the Mandelbrot set
+            if view_rect.contains_point(cursor) {
+                julia_c = window_to_complex(
+                    cursor,
+                    center,
+                    radius,
+                    &render_options.transform,
+                    view_dimensions,
+                );
+            }
+            let julia_options = RenderOptions {
+                supersampling: Supersampling::default(),
+                julia: Some(julia_c),
+                transform: ViewTransform::default(),
+                ..render_options
+            };
+            let image_is_new = julia_render
+                .as_ref()
+                .is_none_or(|(options, iterations, _)| {
+                    *options != julia_options || *iterations != max_iterations
+                });
+            if image_is_new {
+                let (julia_center, julia_radius) = FractalMode::Mandelbrot.home();
+                let image = Renderer::<f64>::render(
+                    &mut julia_renderer,
+                    julia_center,
+                    julia_radius,
+                    max_iterations,
+                    &julia_options,
+                )
+                .unwrap();
+                julia_render = Some((julia_options, max_iterations, image));
+            }
+            // Rendered above if there was no previous image
+            let (_, _, image) = julia_render.as_ref().unwrap();
+            // The preview gets its own histogram rather than the main image's
+            let mut julia_colouring = colouring.clone();
+            julia_colouring.prepare(image, image_is_new);
+            image
+                .write_to_texture(&mut julia_texture, &julia_colouring)
+                .map_err(SdlError::from)?;
+            canvas
+                .copy(&julia_texture, None, julia_pane)
+                .map_err(SdlError::from)?;
+        }
+        if fractal_mode == FractalMode::Mandelbrot && show_rays {
+            let view = (
+                center,
+                radius,
+                view_dimensions,
+                max_iterations,
+                ray_angles.len(),
+            );
+            if traced_view != Some(view) {
+                let pixel_size = 2.0 * radius / view_dimensions.width.get() as f64;
+                traced_rays = ray_angles
+                    .iter()
+                    .map(|angle| trace_ray(angle, pixel_size / 2.0, max_iterations))
+                    .collect();
+                traced_view = Some(view);
+            }
+            canvas.set_draw_color(Color::WHITE);
+            for ray in &traced_rays {
+                // The far end of the ray is all one straight line outside the window
+                let points = ray
+                    .iter()
+                    .skip_while(|&&point| (point - center).norm() > RAY_CLIP_RADIUS * radius)
+                    .map(|&point| {
+                        complex_to_window(
+                            point,
+                            center,
+                            radius,
+                            &render_options.transform,
+                            view_dimensions,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                canvas.draw_lines(&points[..]).map_err(SdlError::from)?;
+            }
+        }
+        let orbit = (show_orbit && fractal_mode.is_escape_time()).then(|| {
+            Orbit::new(
+                window_to_complex(
+                    cursor,
+                    center,
+                    radius,
+                    &render_options.transform,
+                    view_dimensions,
+                ),
+                max_iterations,
+                orbit_formula.as_ref(),
+            )
+        });
+        if let Some(orbit) = &orbit {
+            let points = orbit
+                .points
+                .iter()
+                .map(|&point| {
+                    complex_to_window(
+                        point,
+                        center,
+                        radius,
+                        &render_options.transform,
+                        view_dimensions,
+                    )
+                })
+                .collect::<Vec<_>>();
+            canvas.set_draw_color(Color::YELLOW);
+            canvas.draw_lines(&points[..]).map_err(SdlError::from)?;
+            let markers = points
+                .iter()
+                .map(|point| Rect::from_center(*point, ORBIT_MARKER_SIZE, ORBIT_MARKER_SIZE))
+                .collect::<Vec<_>>();
+            canvas.fill_rects(&markers).map_err(SdlError::from)?;
+        }
+        if show_minimap && fractal_mode.is_escape_time() {
+            let image = minimap.image(applied_formula.as_ref());
+            // The overview gets its own histogram rather than the main image's
+            let mut minimap_colouring = colouring.clone();
+            minimap_colouring.prepare(image, true);
+            image
+                .write_to_texture(&mut minimap_texture, &minimap_colouring)
+                .map_err(SdlError::from)?;
+            let inset = minimap_rect(view_dimensions, minimap.dimensions());
+            canvas
+                .copy(&minimap_texture, None, inset)
+                .map_err(SdlError::from)?;
+            canvas.set_clip_rect(inset);
+            canvas.set_draw_color(Color::RED);
+            match minimap.view_outline(center, radius, view_dimensions, &render_options.transform) {
+                Some(outline) => {
+                    let outline = outline.map(|point| point.offset(inset.x(), inset.y()));
+                    canvas.draw_lines(&outline[..]).map_err(SdlError::from)?;
+                }
+                None => {
+                    let point = minimap.crosshair(center).offset(inset.x(), inset.y());
+                    canvas
+                        .draw_line(
+                            point.offset(-MINIMAP_CROSSHAIR_SIZE, 0),
+                            point.offset(MINIMAP_CROSSHAIR_SIZE, 0),
+                        )
+                        .map_err(SdlError::from)?;
+                    canvas
+                        .draw_line(
+                            point.offset(0, -MINIMAP_CROSSHAIR_SIZE),
+                            point.offset(0, MINIMAP_CROSSHAIR_SIZE),
+                        )
+                        .map_err(SdlError::from)?;
+                }
+            }
+            canvas.set_clip_rect(None);
+            canvas.set_draw_color(Color::WHITE);
+            canvas.draw_rect(inset).map_err(SdlError::from)?;
+        }
+        let julia_status = if split {
+            format!("\nJulia preview: c = {julia_c:.6}")
+        } else {
+            String::new()
+        };
+        let transform_status =
+            if fractal_mode.is_escape_time() && !render_options.transform.is_identity() {
+                format!(
+                    "\nView: rotated {:.1}°, stretch {view_stretch:.3}, skew {view_skew:+.3}",
+                    view_rotation.to_degrees()
+                )
+            } else {
+                String::new()
+            };
+        let orbit_status = orbit.map_or(String::new(), |orbit| format!("\nOrbit: {orbit}"));
+        let formula_status = match (&formula_input, &formula_error) {
+            (Some(input), error) => format!(
+                "\nEditing formula: z = {input}_ (Enter to apply, Esc to cancel){}",
+                error
+                    .as_ref()
+                    .map_or(String::new(), |error| format!("\n{error}"))
+            ),
+            (None, Some(error)) => format!("\nFormula error: {error}"),
+            (None, None) => String::new(),
+        };
         let text = font
             .render(&format!(
-                "{}\nTime to render: {:.2} ms\nMax iterations: {max_iterations}\nCurrent window resolution: {}x{}",
+                "{}\nFractal: {fractal_description}\nTime to render: {:.2} ms\nMax iterations: {max_iterations}{}\nSupersampling: {}\nShading: {}\nPalette: {}\nColour cycling: {}\nOpenCL colouring: {}\nCurrent window resolution: {}x{}{transform_status}{analysis_status}{julia_status}{orbit_status}{formula_status}",
                 match renderer_choice {
                     RendererChoice::Cpu => "Multithreaded Scalar CPU",
                     RendererChoice::Opencl => &opencl_display_string,
                 },
                 duration.as_secs_f64() * 1e3,
+                if adaptive_iterations.is_some() && fractal_mode.is_escape_time() {
+                    " (adaptive)"
+                } else {
+                    ""
+                },
+                render_options.supersampling,
+                match colouring.shading {
+                    Shading::Lighting => format!("{} ({})", colouring.shading, colouring.light),
+                    Shading::OrbitTrap => format!("{} ({orbit_trap})", colouring.shading),
+                    Shading::HistogramEqualised if colouring.histogram_locked => {
+                        format!("{} (locked)", colouring.shading)
+                    }
+                    shading => shading.to_string(),
+                },
+                match (&palette_file, &palette_error) {
+                    (None, _) => "Greyscale".to_string(),
+                    (Some(file), None) => file.path().display().to_string(),
+                    (Some(file), Some(error)) => format!("{} ({error})", file.path().display()),
+                },
+                if colour_cycling {
+                    format!("{colour_cycling_speed:+.2} palettes/s")
+                } else {
+                    "Off".to_string()
+                },
+                match (device_colouring, render_parameters.device_colouring) {
+                    (false, _) => "Off",
+                    (true, true) => "On",
+                    (true, false) => "Unavailable for this renderer or mode",
+                },
                 window_dimensions.width,
                 window_dimensions.height
             ))
@@ -279,8 +1509,169 @@ fn app() -> Result<(), FatalError> {
     Ok(())
 }
 
+#[derive(Default)]
+struct Arguments {
+    /// Configuration file read instead of the one in the XDG configuration directory.
+    config: Option<PathBuf>,
+    palette: Option<PathBuf>,
+    /// Polynomial whose roots the Newton fractal converges to.
+    polynomial: Polynomial,
+    lyapunov: LyapunovParameters,
+    /// Source of the iteration step of the formula mode, parsed once the window is open so errors
+    /// show up there.
+    formula: Option<String>,
+    /// Angles of the external rays drawn over the Mandelbrot set.
+    rays: Vec<ExternalAngle>,
+    /// Preperiod and period of the Misiurewicz points searched for.
+    misiurewicz: Option<(u32, u32)>,
+    /// Whether to run the benchmark suite instead of opening a window.
+    bench: bool,
+    /// Where the benchmark report goes.
+    bench_report: Option<PathBuf>,
+}
+
+/// Parses the value following `option` with `parse`.
+fn polynomial_argument(
+    option: &'static str,
+    value: Option<OsString>,
+    parse: impl FnOnce(&str) -> Result<Polynomial, PolynomialError>,
+) -> Result<Polynomial, ArgumentError> {
+    let value = value.ok_or(ArgumentError::MissingValue(option))?;
+    let value = value.to_str().ok_or_else(|| {
+        ArgumentError::InvalidPolynomial(
+            option,
+            PolynomialError::InvalidNumber(value.to_string_lossy().into_owned()),
+        )
+    })?;
+    parse(value).map_err(|error| ArgumentError::InvalidPolynomial(option, error))
+}
+
+impl Arguments {
+    fn parse(mut arguments: impl Iterator<Item = OsString>) -> Result<Self, ArgumentError> {
+        let mut parsed = Self::default();
+        while let Some(argument) = arguments.next() {
+            match argument.to_str() {
+                Some("--config") => {
+                    parsed.config = Some(
+                        arguments
+                            .next()
+                            .ok_or(ArgumentError::MissingValue("--config"))?
+                            .into(),
+                    )
+                }
+                Some("--palette") => {
+                    parsed.palette = Some(
+                        arguments
+                            .next()
+                            .ok_or(ArgumentError::MissingValue("--palette"))?
+                            .into(),
+                    )
+                }
+                Some("--newton-coefficients") => {
+                    parsed.polynomial =
+                        polynomial_argument("--newton-coefficients", arguments.next(), str::parse)?
+                }
+                Some("--newton-roots") => {
+                    parsed.polynomial =
+                        polynomial_argument("--newton-roots", arguments.next(), |value| {
+                            Polynomial::from_roots(parse_complex_list(value)?)
+                        })?
+                }
+                Some("--formula") => {
+                    let value = arguments
+                        .next()
+                        .ok_or(ArgumentError::MissingValue("--formula"))?;
+                    parsed.formula = Some(value.to_string_lossy().into_owned())
+                }
+                Some("--lyapunov-sequence") => {
+                    let value = arguments
+                        .next()
+                        .ok_or(ArgumentError::MissingValue("--lyapunov-sequence"))?;
+                    parsed.lyapunov.sequence = value
+                        .to_string_lossy()
+                        .parse()
+                        .map_err(ArgumentError::InvalidSequence)?
+                }
+                Some("--lyapunov-warmup") => {
+                    let value = arguments
+                        .next()
+                        .ok_or(ArgumentError::MissingValue("--lyapunov-warmup"))?;
+                    parsed.lyapunov.warmup = value
+                        .to_str()
+                        .and_then(|value| value.parse().ok())
+                        .ok_or(ArgumentError::InvalidNumber("--lyapunov-warmup", value))?
+                }
+                Some("--ray") => {
+                    let value = arguments
+                        .next()
+                        .ok_or(ArgumentError::MissingValue("--ray"))?;
+                    parsed.rays.push(
+                        value
+                            .to_string_lossy()
+                            .parse()
+                            .map_err(ArgumentError::InvalidAngle)?,
+                    )
+                }
+                Some("--misiurewicz") => {
+                    let value = arguments
+                        .next()
+                        .ok_or(ArgumentError::MissingValue("--misiurewicz"))?;
+                    parsed.misiurewicz = Some(
+                        value
+                            .to_str()
+                            .and_then(|value| value.split_once(','))
+                            .and_then(|(preperiod, period)| {
+                                Some((preperiod.trim().parse().ok()?, period.trim().parse().ok()?))
+                            })
+                            .ok_or(ArgumentError::InvalidNumber("--misiurewicz", value))?,
+                    )
+                }
+                Some("--bench") => parsed.bench = true,
+                Some("--bench-report") => {
+                    parsed.bench_report = Some(
+                        arguments
+                            .next()
+                            .ok_or(ArgumentError::MissingValue("--bench-report"))?
+                            .into(),
+                    )
+                }
+                _ => return Err(ArgumentError::Unknown(argument)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
 fn main() -> ExitCode {
+    let arguments = match Arguments::parse(env::args_os().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if arguments.bench {
+        let report_path = arguments
+            .bench_report
+            .unwrap_or_else(|| benchmark::DEFAULT_REPORT_PATH.into());
+        if let Err(error) = benchmark::run(&report_path) {
+            eprintln!("cannot write {}: {error}", report_path.display());
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
+    }
+    // Only a configuration file asked for by name has to exist
+    let config = match arguments.config.clone().or_else(Config::default_path) {
+        Some(path) => match Config::load(&path, arguments.config.is_some()) {
+            Ok(config) => config,
+            Err(error) => {
+                eprintln!("{}: {error}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Config::default(),
+    };
     // TODO: implement error handling
-    app().unwrap();
+    app(arguments, config).unwrap();
     ExitCode::SUCCESS
 }
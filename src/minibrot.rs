@@ -0,0 +1,195 @@
+use num::{Complex, Zero};
+
+use crate::double_double::DoubleDouble;
+
+/// Newton steps tried before giving up on a nucleus.
+const MAX_NEWTON_STEPS: u32 = 64;
+
+/// Relative step size below which Newton's method counts as converged.
+const NEWTON_TOLERANCE: f64 = 1e-30;
+
+/// How far outside the searched box a nucleus may lie before it counts as belonging to a
+/// different atom, in box radii.
+const MAX_NUCLEUS_DISTANCE: f64 = 2.0;
+
+/// Radius of the view that frames a minibrot, in multiples of its estimated size.
+const VIEW_MARGIN: f64 = 4.0;
+
+/// Hyperbolic component of the Mandelbrot set, located by its nucleus, the point whose orbit
+/// returns exactly to 0 after `period` iterations.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Minibrot {
+    pub period: u32,
+    pub nucleus: Complex<DoubleDouble>,
+    /// Rough radius of the component, with the main cardioid about 1.
+    pub size: f64,
+}
+
+impl Minibrot {
+    /// Finds the lowest period atom in the square of half-width `radius` around `center`, checking
+    /// periods up to `max_period`.
+    pub fn find(center: Complex<f64>, radius: f64, max_period: u32) -> Option<Self> {
+        let period = box_period(center, radius, max_period)?;
+        let guess = Complex::new(DoubleDouble::from(center.re), DoubleDouble::from(center.im));
+        let nucleus = nucleus(guess, period)?;
+        let offset = Complex::new(nucleus.re.to_f64(), nucleus.im.to_f64()) - center;
+        if offset.norm() > MAX_NUCLEUS_DISTANCE * radius {
+            return None;
+        }
+        Some(Self {
+            period,
+            nucleus,
+            size: size(nucleus, period),
+        })
+    }
+
+    /// Nucleus rounded to the precision the renderers work in.
+    pub fn center(&self) -> Complex<f64> {
+        Complex::new(self.nucleus.re.to_f64(), self.nucleus.im.to_f64())
+    }
+
+    /// Horizontal radius of a view showing the whole minibrot.
+    pub fn view_radius(&self) -> f64 {
+        self.size * VIEW_MARGIN
+    }
+}
+
+impl std::fmt::Display for Minibrot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.nucleus.im.hi() < 0.0 { '-' } else { '+' };
+        write!(
+            f,
+            "period {} at {} {sign} {}i, size {:.3e}",
+            self.period,
+            self.nucleus.re,
+            self.nucleus.im.abs(),
+            self.size
+        )
+    }
+}
+
+/// Whether the quadrilateral with the given corners, in order, surrounds the origin.
+fn surrounds_origin(corners: &[Complex<f64>; 4]) -> bool {
+    let mut inside = false;
+    for (index, a) in corners.iter().enumerate() {
+        let b = corners[(index + 1) % corners.len()];
+        if (a.im > 0.0) != (b.im > 0.0) {
+            let crossing = a.re - a.im * (b.re - a.re) / (b.im - a.im);
+            if crossing > 0.0 {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Lowest period of an atom in the square of half-width `radius` around `center`, found by
+/// iterating its corners until the image of the square surrounds the origin.
+pub fn box_period(center: Complex<f64>, radius: f64, max_period: u32) -> Option<u32> {
+    let corners = [
+        Complex::new(-radius, -radius),
+        Complex::new(radius, -radius),
+        Complex::new(radius, radius),
+        Complex::new(-radius, radius),
+    ]
+    .map(|offset| center + offset);
+    let mut z = [Complex::<f64>::zero(); 4];
+    for period in 1..=max_period {
+        for (z, c) in z.iter_mut().zip(corners) {
+            *z = *z * *z + c;
+        }
+        if z.iter().any(|z| !z.norm_sqr().is_finite()) {
+            return None;
+        }
+        if surrounds_origin(&z) {
+            return Some(period);
+        }
+    }
+    None
+}
+
+/// Refines `guess` to the nucleus of a component with the given period using Newton's method on
+/// the `period`-th iterate of 0, or `None` if it does not converge.
+pub fn nucleus(guess: Complex<DoubleDouble>, period: u32) -> Option<Complex<DoubleDouble>> {
+    let one = Complex::from(DoubleDouble::from(1.0));
+    let two = DoubleDouble::from(2.0);
+    let mut c = guess;
+    for _ in 0..MAX_NEWTON_STEPS {
+        let mut z = Complex::<DoubleDouble>::zero();
+        let mut derivative = Complex::zero();
+        for _ in 0..period {
+            derivative = z * derivative * two + one;
+            z = z * z + c;
+        }
+        let step = z / derivative;
+        if !(step.re.is_finite() && step.im.is_finite()) {
+            return None;
+        }
+        c = c - step;
+        let step_size = step.norm_sqr().to_f64();
+        if step_size <= c.norm_sqr().to_f64().max(f64::MIN_POSITIVE) * NEWTON_TOLERANCE.powi(2) {
+            return Some(c);
+        }
+    }
+    None
+}
+
+/// Estimated radius of the component with the given nucleus and period, from the multiplier's
+/// derivatives along the periodic orbit.
+pub fn size(nucleus: Complex<DoubleDouble>, period: u32) -> f64 {
+    let one = Complex::from(DoubleDouble::from(1.0));
+    let two = DoubleDouble::from(2.0);
+    let mut z = Complex::<DoubleDouble>::zero();
+    let mut l = one;
+    let mut b = one;
+    for _ in 1..period {
+        z = z * z + nucleus;
+        l = z * l * two;
+        b = b + one / l;
+    }
+    let size = one / (b * l * l);
+    Complex::new(size.re.to_f64(), size.im.to_f64()).norm()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_period_three_minibrot_on_the_needle() {
+        let minibrot = Minibrot::find(Complex::new(-1.75, 0.0), 0.01, 64).unwrap();
+        assert_eq!(minibrot.period, 3);
+        assert!(
+            minibrot
+                .nucleus
+                .re
+                .to_string()
+                .starts_with("-1.75487766624669276004"),
+            "{minibrot}"
+        );
+        assert!(minibrot.nucleus.im.to_f64().abs() < 1e-30, "{minibrot}");
+    }
+
+    #[test]
+    fn box_period_of_known_components() {
+        assert_eq!(box_period(Complex::new(0.0, 0.0), 0.1, 64), Some(1));
+        assert_eq!(box_period(Complex::new(-1.0, 0.0), 0.1, 64), Some(2));
+        assert_eq!(box_period(Complex::new(-0.1225, 0.7448), 0.01, 64), Some(3));
+        assert_eq!(
+            box_period(Complex::new(-1.7548776662, 0.0), 1e-4, 64),
+            Some(3)
+        );
+        // Far outside the set every corner escapes
+        assert_eq!(box_period(Complex::new(3.0, 3.0), 0.1, 64), None);
+    }
+
+    #[test]
+    fn size_of_known_components() {
+        let size_at = |re: f64, period| size(Complex::new(re.into(), 0.0.into()), period);
+        assert_eq!(size_at(0.0, 1), 1.0);
+        assert!((size_at(-1.0, 2) - 0.5).abs() < 1e-12);
+        let nucleus = nucleus(Complex::new((-1.75).into(), 0.0.into()), 3).unwrap();
+        let size = size(nucleus, 3);
+        assert!((0.015..0.025).contains(&size), "{size}");
+    }
+}
@@ -0,0 +1,131 @@
+use num::Complex;
+use sdl2::rect::Point;
+
+use crate::{
+    error::IntegerOverflow,
+    formula::Formula,
+    iteration_image::IterationImage,
+    render::{
+        cpu::ScalarCpuRenderer, pixel_to_complex, transform::ViewTransform, RenderOptions, Renderer,
+    },
+    Dimensions,
+};
+
+/// Width of the smallest view outline drawn; smaller views are marked with a crosshair instead.
+const MIN_VIEW_OUTLINE_SIZE: f64 = 4.0;
+
+/// Iteration limit of the overview, that of the home view. The main view's limit is no use here,
+/// as it grows with zoom depth while the overview never zooms.
+const MAX_ITERATIONS: u32 = 256;
+
+/// Low resolution overview of a whole fractal, rendered once and kept until what it shows changes.
+pub struct MiniMap {
+    renderer: ScalarCpuRenderer,
+    dimensions: Dimensions,
+    center: Complex<f64>,
+    radius: f64,
+    /// The image and the formula it was rendered with.
+    cached: Option<(IterationImage, Option<Formula>)>,
+}
+
+impl MiniMap {
+    /// Overview of the view at `center` with horizontal radius `radius`.
+    pub fn new(
+        dimensions: Dimensions,
+        center: Complex<f64>,
+        radius: f64,
+    ) -> Result<Self, IntegerOverflow> {
+        Ok(Self {
+            renderer: ScalarCpuRenderer::new(dimensions.width, dimensions.height)?,
+            dimensions,
+            center,
+            radius,
+            cached: None,
+        })
+    }
+
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    /// The overview, rendered again only if the formula changed.
+    pub fn image(&mut self, formula: Option<&Formula>) -> &IterationImage {
+        let stale = self
+            .cached
+            .as_ref()
+            .is_none_or(|(_, cached_formula)| cached_formula.as_ref() != formula);
+        if stale {
+            self.renderer.set_formula(formula);
+            let image = Renderer::<f64>::render(
+                &mut self.renderer,
+                self.center,
+                self.radius,
+                MAX_ITERATIONS,
+                &RenderOptions::default(),
+            )
+            .unwrap();
+            self.cached = Some((image, formula.cloned()));
+        }
+        // Filled in above if it was empty
+        &self.cached.as_ref().unwrap().0
+    }
+
+    /// Point of the overview at `(x, y)` relative to its top left corner.
+    pub fn point_at(&self, x: i32, y: i32) -> Complex<f64> {
+        let Dimensions { width, height } = self.dimensions;
+        let x = (x.max(0) as usize).min(width.get() - 1);
+        let y = (y.max(0) as usize).min(height.get() - 1);
+        pixel_to_complex(y * width.get() + x, self.center, self.radius, width, height)
+    }
+
+    fn complex_to_point(&self, z: Complex<f64>) -> Point {
+        let (width, height) = (
+            self.dimensions.width.get() as f64,
+            self.dimensions.height.get() as f64,
+        );
+        let vertical_radius = self.radius * height / width;
+        let x = (z.re - self.center.re + self.radius) / (2.0 * self.radius) * width;
+        let y = (self.center.im + vertical_radius - z.im) / (2.0 * vertical_radius) * height;
+        // Clamped so views far outside the overview still give sensible coordinates
+        Point::new(
+            x.clamp(-width, 2.0 * width) as i32,
+            y.clamp(-height, 2.0 * height) as i32,
+        )
+    }
+
+    /// Outline of the view at `center` with horizontal radius `radius` shown in a window of
+    /// `window_dimensions` and transformed by `transform`, as a closed line relative to the
+    /// overview's top left corner, or `None` if it is too small to see and only a crosshair at
+    /// `center` should mark it.
+    pub fn view_outline(
+        &self,
+        center: Complex<f64>,
+        radius: f64,
+        window_dimensions: Dimensions,
+        transform: &ViewTransform,
+    ) -> Option<[Point; 5]> {
+        let vertical_radius =
+            radius * window_dimensions.height.get() as f64 / window_dimensions.width.get() as f64;
+        let [top_left, top_right, bottom_right, bottom_left] = [
+            (-radius, vertical_radius),
+            (radius, vertical_radius),
+            (radius, -vertical_radius),
+            (-radius, -vertical_radius),
+        ]
+        .map(|(re, im)| self.complex_to_point(center + transform.apply(Complex::new(re, im))));
+        let width =
+            f64::from(top_right.x() - top_left.x()).hypot(f64::from(top_right.y() - top_left.y()));
+        (width >= MIN_VIEW_OUTLINE_SIZE).then_some([
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+            top_left,
+        ])
+    }
+
+    /// Position of `center` relative to the overview's top left corner.
+    pub fn crosshair(&self, center: Complex<f64>) -> Point {
+        self.complex_to_point(center)
+    }
+}
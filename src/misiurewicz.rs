@@ -0,0 +1,170 @@
+use num::{Complex, Zero};
+
+use crate::double_double::DoubleDouble;
+
+const MAX_NEWTON_STEPS: u32 = 64;
+
+/// Relative step size below which Newton's method counts as converged.
+const NEWTON_TOLERANCE: f64 = 1e-28;
+
+/// Relative distance between orbit points below which they count as the same point, used to
+/// reject points whose orbit becomes periodic earlier, or with a shorter cycle, than asked for.
+const COINCIDENCE_TOLERANCE: f64 = 1e-12;
+
+/// Point on the boundary of the Mandelbrot set whose critical orbit is strictly preperiodic,
+/// landing on a cycle of length `period` after `preperiod` iterations.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct MisiurewiczPoint {
+    pub preperiod: u32,
+    pub period: u32,
+    pub point: Complex<DoubleDouble>,
+}
+
+impl MisiurewiczPoint {
+    /// Refines `guess` with Newton's method on the difference between the orbit's
+    /// `preperiod + period`-th and `preperiod`-th points, divided by the same difference for every
+    /// proper divisor of `period`. That difference also vanishes where the cycle is shorter, and
+    /// dividing those roots out keeps Newton's method from converging to them. Gives `None` if it
+    /// does not converge, if `preperiod` or `period` is 0, or if the point found still has a
+    /// shorter preperiod or period.
+    pub fn find(guess: Complex<f64>, preperiod: u32, period: u32) -> Option<Self> {
+        if preperiod == 0 || period == 0 {
+            return None;
+        }
+        let one = Complex::from(DoubleDouble::from(1.0));
+        let two = DoubleDouble::from(2.0);
+        let mut c = Complex::new(DoubleDouble::from(guess.re), DoubleDouble::from(guess.im));
+        for _ in 0..MAX_NEWTON_STEPS {
+            let mut z = Complex::<DoubleDouble>::zero();
+            let mut derivative = Complex::zero();
+            let (mut preperiodic, mut preperiodic_derivative) = (z, derivative);
+            // Logarithmic derivative of the product of the factors divided out
+            let mut divided = Complex::zero();
+            for iteration in 0..preperiod + period {
+                if iteration == preperiod {
+                    (preperiodic, preperiodic_derivative) = (z, derivative);
+                } else if iteration > preperiod && period.is_multiple_of(iteration - preperiod) {
+                    divided = divided + (derivative - preperiodic_derivative) / (z - preperiodic);
+                }
+                derivative = z * derivative * two + one;
+                z = z * z + c;
+            }
+            // The quotient's value over its derivative, from the logarithmic derivatives
+            let difference = z - preperiodic;
+            let step = difference / (derivative - preperiodic_derivative - difference * divided);
+            if !(step.re.is_finite() && step.im.is_finite()) {
+                return None;
+            }
+            c = c - step;
+            if step.norm_sqr().to_f64()
+                <= c.norm_sqr().to_f64().max(f64::MIN_POSITIVE) * NEWTON_TOLERANCE.powi(2)
+            {
+                let point = Self {
+                    preperiod,
+                    period,
+                    point: c,
+                };
+                return (!point.has_shorter_preperiod() && !point.has_shorter_period())
+                    .then_some(point);
+            }
+        }
+        None
+    }
+
+    /// Whether the orbit already repeats one iteration earlier than the preperiod says.
+    fn has_shorter_preperiod(&self) -> bool {
+        let c = Complex::new(self.point.re.to_f64(), self.point.im.to_f64());
+        let mut z = Complex::<f64>::zero();
+        let mut earlier = z;
+        for iteration in 0..self.preperiod - 1 + self.period {
+            if iteration == self.preperiod - 1 {
+                earlier = z;
+            }
+            z = z * z + c;
+        }
+        coincide(z, earlier)
+    }
+
+    /// Whether the orbit's cycle is a proper divisor of the period long.
+    fn has_shorter_period(&self) -> bool {
+        let c = Complex::new(self.point.re.to_f64(), self.point.im.to_f64());
+        let mut z = Complex::<f64>::zero();
+        let mut preperiodic = z;
+        for iteration in 0..self.preperiod + self.period {
+            if iteration == self.preperiod {
+                preperiodic = z;
+            } else if iteration > self.preperiod
+                && self.period.is_multiple_of(iteration - self.preperiod)
+                && coincide(z, preperiodic)
+            {
+                return true;
+            }
+            z = z * z + c;
+        }
+        false
+    }
+
+    /// Nearest point the renderers can center on.
+    pub fn center(&self) -> Complex<f64> {
+        Complex::new(self.point.re.to_f64(), self.point.im.to_f64())
+    }
+}
+
+fn coincide(a: Complex<f64>, b: Complex<f64>) -> bool {
+    (a - b).norm() <= COINCIDENCE_TOLERANCE * a.norm().max(1.0)
+}
+
+impl std::fmt::Display for MisiurewiczPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.point.im.hi() < 0.0 { '-' } else { '+' };
+        write!(
+            f,
+            "M{},{} at {} {sign} {}i",
+            self.preperiod,
+            self.period,
+            self.point.re,
+            self.point.im.abs()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_tip_of_the_needle() {
+        let point = MisiurewiczPoint::find(Complex::new(-1.9, 0.0), 2, 1).unwrap();
+        assert!(
+            (point.center() - Complex::new(-2.0, 0.0)).norm() < 1e-14,
+            "{point}"
+        );
+    }
+
+    #[test]
+    fn finds_i() {
+        let point = MisiurewiczPoint::find(Complex::new(0.05, 0.95), 2, 2).unwrap();
+        assert!(
+            (point.center() - Complex::new(0.0, 1.0)).norm() < 1e-14,
+            "{point}"
+        );
+    }
+
+    #[test]
+    fn rejects_points_with_a_shorter_period() {
+        // The only nearby root of the equation is -2, which is M2,1 rather than M2,2
+        assert_eq!(MisiurewiczPoint::find(Complex::new(-1.99, 0.0), 2, 2), None);
+    }
+
+    #[test]
+    fn rejects_points_with_a_shorter_preperiod() {
+        // -2 lands on its fixed point after 2 iterations, not 3
+        assert_eq!(MisiurewiczPoint::find(Complex::new(-2.0, 0.0), 3, 1), None);
+    }
+
+    #[test]
+    fn rejects_zero_preperiod_or_period() {
+        assert_eq!(MisiurewiczPoint::find(Complex::new(-2.0, 0.0), 0, 1), None);
+        assert_eq!(MisiurewiczPoint::find(Complex::new(-2.0, 0.0), 2, 0), None);
+    }
+}
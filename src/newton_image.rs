@@ -0,0 +1,71 @@
+use std::num::NonZeroUsize;
+
+use sdl2::render::Texture;
+
+use crate::{
+    error::{InvalidBufferSize, WriteToTextureError},
+    render::newton::RootHit,
+};
+
+/// Which root Newton's method converged to from every pixel, and how fast.
+pub struct NewtonImage {
+    buffer: Vec<RootHit>,
+    width: NonZeroUsize,
+    root_count: usize,
+    max_iterations: u32,
+}
+
+impl NewtonImage {
+    pub fn from_buffer(
+        buffer: Vec<RootHit>,
+        width: NonZeroUsize,
+        root_count: usize,
+        max_iterations: u32,
+    ) -> Result<Self, InvalidBufferSize> {
+        if buffer.is_empty() || !buffer.len().is_multiple_of(width.get()) {
+            return Err(InvalidBufferSize);
+        }
+        Ok(Self {
+            buffer,
+            width,
+            root_count,
+            max_iterations,
+        })
+    }
+
+    /// Gives every root its own hue, darkening pixels the more iterations they needed. Pixels that
+    /// did not converge are black.
+    fn colour(&self, hit: RootHit) -> [u8; 3] {
+        if hit.root == RootHit::NO_ROOT {
+            return [0; 3];
+        }
+        // Convergence is usually quick, so speed is measured on a logarithmic scale
+        let brightness =
+            1.0 - f64::from(hit.iterations).ln_1p() / f64::from(self.max_iterations.max(1)).ln_1p();
+        let hue = hit.root as f64 / self.root_count.max(1) as f64;
+        hue_to_rgb(hue).map(|channel| (channel * brightness * 255.0).round() as u8)
+    }
+
+    pub fn write_to_texture(&self, texture: &mut Texture) -> Result<(), WriteToTextureError> {
+        let query = texture.query();
+        let height = self.buffer.len() / self.width.get();
+        if self.width.get() != query.width as usize || height != query.height as usize {
+            return Err(WriteToTextureError::DimensionsDoNotMatch);
+        }
+        let texture_data = self
+            .buffer
+            .iter()
+            .flat_map(|&hit| self.colour(hit))
+            .collect::<Vec<_>>();
+        texture.update(None, &texture_data, self.width.get() * 3)?;
+        Ok(())
+    }
+}
+
+/// Fully saturated colour of `hue` from 0 to 1, with channels from 0 to 1.
+fn hue_to_rgb(hue: f64) -> [f64; 3] {
+    [5.0, 3.0, 1.0].map(|n: f64| {
+        let k = (n + hue * 6.0).rem_euclid(6.0);
+        1.0 - k.min(4.0 - k).clamp(0.0, 1.0)
+    })
+}
@@ -0,0 +1,70 @@
+use num::{Complex, Zero};
+
+use crate::formula::CompiledFormula;
+
+/// Longest cycle looked for at the end of a bounded orbit.
+const MAX_PERIOD: usize = 1024;
+
+/// Distance below which two orbit points count as the same point of a cycle.
+const PERIOD_TOLERANCE: f64 = 1e-9;
+
+/// The orbit of 0 under the escape time iteration for a single c.
+pub struct Orbit {
+    /// Every iterate from z₀ = 0 up to and including the first escaped one.
+    pub points: Vec<Complex<f64>>,
+    /// Iteration at which |z| first exceeded 2, or `None` if it never did.
+    pub escape_iteration: Option<u32>,
+    /// Length of the cycle the orbit settled into, if it did within the iteration limit.
+    pub period: Option<usize>,
+}
+
+impl Orbit {
+    /// Iterates z² + c, or `formula` if given, like the escape time renderers do.
+    pub fn new(c: Complex<f64>, max_iterations: u32, formula: Option<&CompiledFormula>) -> Self {
+        let mut z = Complex::zero();
+        let mut points = vec![z];
+        let mut escape_iteration = None;
+        for iteration in 1..=max_iterations {
+            z = match formula {
+                Some(formula) => formula.evaluate(z, c),
+                None => z * z + c,
+            };
+            points.push(z);
+            if z.norm_sqr() >= 4.0 {
+                escape_iteration = Some(iteration);
+                break;
+            }
+        }
+        let period = escape_iteration.map_or_else(|| detect_period(&points), |_| None);
+        Self {
+            points,
+            escape_iteration,
+            period,
+        }
+    }
+
+    pub fn final_magnitude(&self) -> f64 {
+        // There is always at least z₀
+        self.points.last().unwrap().norm()
+    }
+}
+
+impl std::fmt::Display for Orbit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.escape_iteration, self.period) {
+            (Some(iteration), _) => write!(f, "escaped at iteration {iteration}")?,
+            (None, Some(period)) => write!(f, "bounded, period {period}")?,
+            (None, None) => write!(f, "bounded, no period detected")?,
+        }
+        write!(f, ", final |z| = {:.6}", self.final_magnitude())
+    }
+}
+
+/// Smallest p for which the last two points equal the ones p iterations before them.
+fn detect_period(points: &[Complex<f64>]) -> Option<usize> {
+    let last = points.len() - 1;
+    (1..=MAX_PERIOD.min(last.saturating_sub(1))).find(|&period| {
+        (points[last] - points[last - period]).norm() < PERIOD_TOLERANCE
+            && (points[last - 1] - points[last - 1 - period]).norm() < PERIOD_TOLERANCE
+    })
+}
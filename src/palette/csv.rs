@@ -0,0 +1,68 @@
+use crate::error::PaletteError;
+
+use super::{lines, parse_number, Palette};
+
+struct Stop {
+    position: f64,
+    colour: [f64; 3],
+}
+
+/// Parses a CSV of `position,red,green,blue` stops with positions from 0 to 1 in ascending order
+/// and colour components from 0 to 255. A header line is allowed.
+pub fn parse(source: &str) -> Result<Palette, PaletteError> {
+    let mut stops = Vec::<Stop>::new();
+    for (index, (line, text)) in lines(source).enumerate() {
+        let fields = text.split(',').collect::<Vec<_>>();
+        if index == 0 && fields[0].trim().parse::<f64>().is_err() {
+            continue;
+        }
+        if fields.len() != 4 {
+            return Err(PaletteError::Syntax {
+                line,
+                message: format!(
+                    "expected position, red, green and blue, found {} fields",
+                    fields.len()
+                ),
+            });
+        }
+        let position = parse_number::<f64>(line, fields[0], "a position from 0 to 1")?;
+        if !(0.0..=1.0).contains(&position) {
+            return Err(PaletteError::Syntax {
+                line,
+                message: format!("position {position} is outside the range from 0 to 1"),
+            });
+        }
+        if stops.last().is_some_and(|last| position < last.position) {
+            return Err(PaletteError::Syntax {
+                line,
+                message: format!("position {position} is smaller than the previous stop's"),
+            });
+        }
+        let mut colour = [0.0; 3];
+        for (channel, field) in colour.iter_mut().zip(&fields[1..]) {
+            *channel = f64::from(parse_number::<u8>(
+                line,
+                field,
+                "a colour component from 0 to 255",
+            )?) / 255.0;
+        }
+        stops.push(Stop { position, colour });
+    }
+    if stops.is_empty() {
+        return Err(PaletteError::Empty);
+    }
+    Ok(Palette::from_gradient(|t| {
+        let next = stops.partition_point(|stop| stop.position <= t);
+        match (stops.get(next.wrapping_sub(1)), stops.get(next)) {
+            (Some(previous), Some(next)) => {
+                let fraction = (t - previous.position) / (next.position - previous.position);
+                [0, 1, 2].map(|channel| {
+                    previous.colour[channel]
+                        + (next.colour[channel] - previous.colour[channel]) * fraction
+                })
+            }
+            (Some(stop), None) | (None, Some(stop)) => stop.colour,
+            (None, None) => unreachable!(),
+        }
+    }))
+}
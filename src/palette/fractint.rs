@@ -0,0 +1,28 @@
+use crate::error::PaletteError;
+
+use super::{lines, parse_number, Palette};
+
+/// Parses a Fractint `.map` file: one `red green blue` triple from 0 to 255 per line, with
+/// anything after the third number treated as a comment.
+pub fn parse(source: &str) -> Result<Palette, PaletteError> {
+    let colours = lines(source)
+        .map(|(line, text)| {
+            let fields = text.split_whitespace().take(3).collect::<Vec<_>>();
+            if fields.len() < 3 {
+                return Err(PaletteError::Syntax {
+                    line,
+                    message: format!(
+                        "expected red, green and blue components, found {}",
+                        fields.len()
+                    ),
+                });
+            }
+            let mut colour = [0; 3];
+            for (channel, field) in colour.iter_mut().zip(fields) {
+                *channel = parse_number(line, field, "a colour component from 0 to 255")?;
+            }
+            Ok(colour)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Palette::from_colours(colours)
+}
@@ -0,0 +1,188 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use crate::error::PaletteError;
+
+use super::{lines, parse_number, Palette};
+
+/// How colours are blended between the ends of a segment, numbered as in the file format.
+#[derive(Clone, Copy)]
+enum Blending {
+    Linear,
+    Curved,
+    Sine,
+    SphereIncreasing,
+    SphereDecreasing,
+    Step,
+}
+
+struct Segment {
+    left: f64,
+    middle: f64,
+    right: f64,
+    left_colour: [f64; 3],
+    right_colour: [f64; 3],
+    blending: Blending,
+}
+
+impl Segment {
+    fn colour(&self, t: f64) -> [f64; 3] {
+        let width = self.right - self.left;
+        let (position, middle) = if width > 0.0 {
+            ((t - self.left) / width, (self.middle - self.left) / width)
+        } else {
+            (0.5, 0.5)
+        };
+        let linear = if position <= middle {
+            if middle > 0.0 {
+                0.5 * position / middle
+            } else {
+                0.0
+            }
+        } else if middle < 1.0 {
+            0.5 + 0.5 * (position - middle) / (1.0 - middle)
+        } else {
+            1.0
+        };
+        let factor = match self.blending {
+            Blending::Linear => linear,
+            Blending::Curved => position.powf(0.5_f64.ln() / middle.max(f64::EPSILON).ln()),
+            Blending::Sine => ((PI * linear - FRAC_PI_2).sin() + 1.0) / 2.0,
+            Blending::SphereIncreasing => (1.0 - (linear - 1.0).powi(2)).sqrt(),
+            Blending::SphereDecreasing => 1.0 - (1.0 - linear.powi(2)).sqrt(),
+            Blending::Step => {
+                if position < middle {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        };
+        [0, 1, 2].map(|channel| {
+            self.left_colour[channel]
+                + (self.right_colour[channel] - self.left_colour[channel]) * factor
+        })
+    }
+}
+
+fn parse_segment(line: usize, text: &str) -> Result<Segment, PaletteError> {
+    let fields = text.split_whitespace().collect::<Vec<_>>();
+    if !(13..=15).contains(&fields.len()) {
+        return Err(PaletteError::Syntax {
+            line,
+            message: format!("expected 13 to 15 segment fields, found {}", fields.len()),
+        });
+    }
+    let numbers = fields[..11]
+        .iter()
+        .map(|field| parse_number::<f64>(line, field, "a number"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (left, middle, right) = (numbers[0], numbers[1], numbers[2]);
+    if !(0.0 <= left && left <= middle && middle <= right && right <= 1.0) {
+        return Err(PaletteError::Syntax {
+            line,
+            message: "segment positions must satisfy 0 <= left <= middle <= right <= 1".into(),
+        });
+    }
+    if numbers[3..]
+        .iter()
+        .any(|component| !(0.0..=1.0).contains(component))
+    {
+        return Err(PaletteError::Syntax {
+            line,
+            message: "colour components must be between 0 and 1".into(),
+        });
+    }
+    let blending = match parse_number::<u8>(line, fields[11], "a blending function")? {
+        0 => Blending::Linear,
+        1 => Blending::Curved,
+        2 => Blending::Sine,
+        3 => Blending::SphereIncreasing,
+        4 => Blending::SphereDecreasing,
+        5 => Blending::Step,
+        other => {
+            return Err(PaletteError::Syntax {
+                line,
+                message: format!("unknown blending function {other}"),
+            })
+        }
+    };
+    match parse_number::<u8>(line, fields[12], "a colouring type")? {
+        0 => (),
+        1 | 2 => {
+            return Err(PaletteError::Syntax {
+                line,
+                message: "HSV colouring is not supported, only RGB".into(),
+            })
+        }
+        other => {
+            return Err(PaletteError::Syntax {
+                line,
+                message: format!("unknown colouring type {other}"),
+            })
+        }
+    }
+    if fields[13..].iter().any(|&colour_type| colour_type != "0") {
+        return Err(PaletteError::Syntax {
+            line,
+            message: "foreground and background endpoint colours are not supported".into(),
+        });
+    }
+    Ok(Segment {
+        left,
+        middle,
+        right,
+        // Alpha is ignored
+        left_colour: [numbers[3], numbers[4], numbers[5]],
+        right_colour: [numbers[7], numbers[8], numbers[9]],
+        blending,
+    })
+}
+
+/// Parses a GIMP `.ggr` gradient with RGB segments. Opacity is ignored.
+pub fn parse(source: &str) -> Result<Palette, PaletteError> {
+    let mut lines = lines(source).peekable();
+    match lines.next() {
+        Some((_, "GIMP Gradient")) => (),
+        Some((line, _)) => {
+            return Err(PaletteError::Syntax {
+                line,
+                message: "expected `GIMP Gradient` header".into(),
+            })
+        }
+        None => return Err(PaletteError::Empty),
+    }
+    lines.next_if(|(_, text)| text.starts_with("Name:"));
+    let (line, text) = lines.next().ok_or(PaletteError::Empty)?;
+    let segment_count = parse_number::<usize>(line, text, "the number of segments")?;
+    let segments = lines
+        .map(|(line, text)| parse_segment(line, text))
+        .collect::<Result<Vec<_>, _>>()?;
+    if segments.len() != segment_count {
+        return Err(PaletteError::Syntax {
+            line,
+            message: format!(
+                "header announces {segment_count} segments, found {}",
+                segments.len()
+            ),
+        });
+    }
+    let first = segments.first().ok_or(PaletteError::Empty)?;
+    let last = segments.last().ok_or(PaletteError::Empty)?;
+    if first.left != 0.0
+        || last.right != 1.0
+        || segments
+            .windows(2)
+            .any(|pair| pair[0].right != pair[1].left)
+    {
+        return Err(PaletteError::Syntax {
+            line,
+            message: "segments must cover the range from 0 to 1 without gaps".into(),
+        });
+    }
+    Ok(Palette::from_gradient(|t| {
+        let index = segments
+            .partition_point(|segment| segment.right < t)
+            .min(segments.len() - 1);
+        segments[index].colour(t)
+    }))
+}
@@ -0,0 +1,130 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::error::PaletteError;
+
+mod csv;
+mod fractint;
+mod gimp;
+
+/// Number of entries gradients with continuous stops are sampled into.
+const GRADIENT_RESOLUTION: usize = 1024;
+
+/// Colour lookup table spanning the range from 0 to 1.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Palette {
+    colours: Vec<[u8; 3]>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            colours: (0..=255).map(|value| [value; 3]).collect(),
+        }
+    }
+}
+
+impl Palette {
+    fn from_colours(colours: Vec<[u8; 3]>) -> Result<Self, PaletteError> {
+        if colours.is_empty() {
+            return Err(PaletteError::Empty);
+        }
+        Ok(Self { colours })
+    }
+
+    /// Samples a gradient given as a function of a position from 0 to 1 into a lookup table.
+    fn from_gradient(gradient: impl Fn(f64) -> [f64; 3]) -> Self {
+        Self {
+            colours: (0..GRADIENT_RESOLUTION)
+                .map(|index| {
+                    let rgb = gradient(index as f64 / (GRADIENT_RESOLUTION - 1) as f64);
+                    rgb.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+                })
+                .collect(),
+        }
+    }
+
+    /// Loads a Fractint `.map`, GIMP `.ggr` or `.csv` gradient, picked by file extension.
+    pub fn load(path: &Path) -> Result<Self, PaletteError> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        let parse = match extension.as_deref() {
+            Some("map") => fractint::parse,
+            Some("ggr") => gimp::parse,
+            Some("csv") => csv::parse,
+            _ => return Err(PaletteError::UnsupportedFormat(path.to_path_buf())),
+        };
+        parse(&fs::read_to_string(path)?)
+    }
+
+    /// Red, green and blue bytes of every entry in order.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.colours.as_flattened()
+    }
+
+    /// Colour at `t`, clamped to the range from 0 to 1, with the palette rotated by `offset` times
+    /// its length.
+    pub fn colour(&self, t: f64, offset: f64) -> [u8; 3] {
+        let index = (t.clamp(0.0, 1.0) * (self.colours.len() - 1) as f64) as usize;
+        let offset = (offset.rem_euclid(1.0) * self.colours.len() as f64) as usize;
+        self.colours[(index + offset) % self.colours.len()]
+    }
+}
+
+/// A palette file that is reloaded whenever it changes on disk.
+pub struct PaletteFile {
+    path: PathBuf,
+    /// Modification time seen by the last `poll`, `None` before the first one.
+    modified: Option<Option<SystemTime>>,
+}
+
+impl PaletteFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            modified: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads the palette if the file was modified since the last call, including the first one.
+    pub fn poll(&mut self) -> Option<Result<Palette, PaletteError>> {
+        let modified = fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+        Some(Palette::load(&self.path))
+    }
+}
+
+/// Splits `source` into trimmed, non-empty lines numbered from 1, skipping `#` comments. The
+/// configuration file is read the same way.
+pub(crate) fn lines(source: &str) -> impl Iterator<Item = (usize, &str)> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn parse_number<T: std::str::FromStr>(
+    line: usize,
+    field: &str,
+    what: &str,
+) -> Result<T, PaletteError> {
+    field.trim().parse().map_err(|_| PaletteError::Syntax {
+        line,
+        message: format!("expected {what}, found `{}`", field.trim()),
+    })
+}
@@ -0,0 +1,48 @@
+// Helpers shared by every OpenCL program
+
+double lerp_inverse(double t, double min, double max) {
+    return (t - min) / (max - min);
+}
+
+double map(double value, double from_source, double to_source, double from_target, double to_target) {
+    return mix(from_target, to_target, lerp_inverse(value, from_source, to_source));
+}
+
+double2 pixel_to_complex(ulong width, ulong height, double center_re, double center_im, double radius, ulong pixel, double2 offset) {
+    const double width_double = (double) width;
+    const double height_double = (double) height;
+
+    const double max_offset_re = radius;
+    const double max_offset_im = radius * height_double / width_double;
+
+    const double bottom_left_re = center_re - max_offset_re;
+    const double bottom_left_im = center_im - max_offset_im;
+    const double top_right_re = center_re + max_offset_re;
+    const double top_right_im = center_im + max_offset_im;
+
+    const ulong x = pixel % width, y = height - pixel / width - 1;
+
+    return (double2) (
+        map(x + offset.x, 0.0, width_double, bottom_left_re, top_right_re),
+        map(y + offset.y, 0.0, height_double, bottom_left_im, top_right_im)
+    );
+}
+
+// Applies the row-major matrix `transform` to the offset of `point` from `center`, leaving points
+// untouched for the identity. Must match `ViewTransform::transform_point` on the host
+double2 transform_point(double2 point, double2 center, double4 transform) {
+    if (transform.s0 == 1.0 && transform.s1 == 0.0 && transform.s2 == 0.0 && transform.s3 == 1.0) {
+        return point;
+    }
+    const double2 offset = point - center;
+    return center + (double2) (
+        transform.s0 * offset.x + transform.s1 * offset.y,
+        transform.s2 * offset.x + transform.s3 * offset.y
+    );
+}
+
+// The square root of how much `transform` stretches areas. Must match `ViewTransform::scale` on
+// the host
+double transform_scale(double4 transform) {
+    return sqrt(fabs(transform.s0 * transform.s3 - transform.s1 * transform.s2));
+}
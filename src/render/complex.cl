@@ -0,0 +1,110 @@
+// Complex arithmetic on `double2` values, matching the `num` functions the host uses
+
+double2 complex_multiply(double2 a, double2 b) {
+    return (double2) (a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+double2 complex_divide(double2 a, double2 b) {
+    return (double2) (a.x * b.x + a.y * b.y, a.y * b.x - a.x * b.y) / dot(b, b);
+}
+
+// Raises to an integer power by repeated squaring, like `Complex::powi`
+double2 complex_powi(double2 z, int exponent) {
+    if (exponent < 0) {
+        return complex_divide((double2) (1.0, 0.0), complex_powi(z, -exponent));
+    }
+    double2 result = (double2) (1.0, 0.0);
+    double2 base = z;
+    for (uint remaining = (uint) exponent; remaining > 0; remaining >>= 1) {
+        if (remaining & 1) {
+            result = complex_multiply(result, base);
+        }
+        base = complex_multiply(base, base);
+    }
+    return result;
+}
+
+double2 complex_exp(double2 z) {
+    return exp(z.x) * (double2) (cos(z.y), sin(z.y));
+}
+
+double2 complex_log(double2 z) {
+    return (double2) (log(length(z)), atan2(z.y, z.x));
+}
+
+double2 complex_pow(double2 base, double2 exponent) {
+    if (exponent.x == 0.0 && exponent.y == 0.0) {
+        return (double2) (1.0, 0.0);
+    }
+    return complex_exp(complex_multiply(exponent, complex_log(base)));
+}
+
+double2 complex_sqrt(double2 z) {
+    const double modulus = length(z);
+    return (double2) (sqrt((modulus + z.x) / 2.0), copysign(sqrt((modulus - z.x) / 2.0), z.y));
+}
+
+double2 complex_sin(double2 z) {
+    return (double2) (sin(z.x) * cosh(z.y), cos(z.x) * sinh(z.y));
+}
+
+double2 complex_cos(double2 z) {
+    return (double2) (cos(z.x) * cosh(z.y), -sin(z.x) * sinh(z.y));
+}
+
+double2 complex_tan(double2 z) {
+    return complex_divide(complex_sin(z), complex_cos(z));
+}
+
+double2 complex_sinh(double2 z) {
+    return (double2) (sinh(z.x) * cos(z.y), cosh(z.x) * sin(z.y));
+}
+
+double2 complex_cosh(double2 z) {
+    return (double2) (cosh(z.x) * cos(z.y), sinh(z.x) * sin(z.y));
+}
+
+double2 complex_tanh(double2 z) {
+    return complex_divide(complex_sinh(z), complex_cosh(z));
+}
+
+double2 complex_abs(double2 z) {
+    return (double2) (length(z), 0.0);
+}
+
+double2 complex_norm(double2 z) {
+    return (double2) (dot(z, z), 0.0);
+}
+
+double2 complex_arg(double2 z) {
+    return (double2) (atan2(z.y, z.x), 0.0);
+}
+
+double2 complex_re(double2 z) {
+    return (double2) (z.x, 0.0);
+}
+
+double2 complex_im(double2 z) {
+    return (double2) (z.y, 0.0);
+}
+
+double2 complex_conj(double2 z) {
+    return (double2) (z.x, -z.y);
+}
+
+// Comparisons look at real parts and return 1 or 0, like `BinaryOperator::apply`
+double2 complex_less(double2 a, double2 b) {
+    return (double2) (a.x < b.x ? 1.0 : 0.0, 0.0);
+}
+
+double2 complex_less_or_equal(double2 a, double2 b) {
+    return (double2) (a.x <= b.x ? 1.0 : 0.0, 0.0);
+}
+
+double2 complex_equal(double2 a, double2 b) {
+    return (double2) (a.x == b.x && a.y == b.y ? 1.0 : 0.0, 0.0);
+}
+
+double2 complex_not_equal(double2 a, double2 b) {
+    return (double2) (a.x != b.x || a.y != b.y ? 1.0 : 0.0, 0.0);
+}
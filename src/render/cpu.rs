@@ -1,16 +1,33 @@
 use std::num::NonZeroUsize;
 
 use num::{traits::float::FloatCore, Complex, Zero};
-use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+use rayon::{
+    iter::{
+        IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator,
+        ParallelIterator,
+    },
+    slice::ParallelSliceMut,
+};
 
-use crate::{error::IntegerOverflow, iteration_image::IterationImage, Dimensions};
+use crate::{
+    error::IntegerOverflow,
+    formula::{CompiledFormula, Formula},
+    iteration_image::IterationImage,
+    Dimensions,
+};
 
-use super::Renderer;
+use super::{
+    orbit_trap::TrapHit,
+    supersampling::{edge_pixels, MAX_SAMPLES_PER_PIXEL},
+    RenderOptions, Renderer,
+};
 
 pub struct ScalarCpuRenderer {
     width: NonZeroUsize,
     height: NonZeroUsize,
-    iteration_image_buffer: Vec<u32>,
+    sample_buffer: Vec<SampleOutput>,
+    /// Iteration step replacing z² + c.
+    formula: Option<CompiledFormula>,
 }
 
 impl ScalarCpuRenderer {
@@ -18,10 +35,8 @@ impl ScalarCpuRenderer {
         Ok(Self {
             width,
             height,
-            iteration_image_buffer: vec![
-                0;
-                width.checked_mul(height).ok_or(IntegerOverflow)?.get()
-            ],
+            sample_buffer: vec![SampleOutput::default(); pixel_count(width, height)?],
+            formula: None,
         })
     }
 
@@ -29,12 +44,24 @@ impl ScalarCpuRenderer {
         &mut self,
         Dimensions { width, height }: Dimensions,
     ) -> Result<(), IntegerOverflow> {
-        self.iteration_image_buffer =
-            vec![0; width.checked_mul(height).ok_or(IntegerOverflow)?.get()];
+        self.sample_buffer = vec![SampleOutput::default(); pixel_count(width, height)?];
         self.width = width;
         self.height = height;
         Ok(())
     }
+
+    /// Iterates `formula` instead of z² + c, or z² + c again for `None`.
+    pub fn set_formula(&mut self, formula: Option<&Formula>) {
+        self.formula = formula.map(Formula::compile);
+    }
+}
+
+fn pixel_count(width: NonZeroUsize, height: NonZeroUsize) -> Result<usize, IntegerOverflow> {
+    let pixel_count = width.checked_mul(height).ok_or(IntegerOverflow)?;
+    pixel_count
+        .checked_mul(NonZeroUsize::new(MAX_SAMPLES_PER_PIXEL).unwrap())
+        .ok_or(IntegerOverflow)?;
+    Ok(pixel_count.get())
 }
 
 fn compose<A, B, C, F: Fn(A) -> B, G: Fn(B) -> C>(f: F, g: G) -> impl Fn(A) -> C {
@@ -57,6 +84,120 @@ fn map<T: FloatCore>(value: T, from_source: T, to_source: T, from_target: T, to_
     )
 }
 
+#[derive(Clone, Copy, Default)]
+struct SampleOutput {
+    iterations: u32,
+    distance: f32,
+    normal: [f32; 2],
+    trap: TrapHit,
+}
+
+fn to_f64<T: FloatCore>(value: Complex<T>) -> Complex<f64> {
+    Complex::new(
+        value.re.to_f64().unwrap_or(f64::INFINITY),
+        value.im.to_f64().unwrap_or(f64::INFINITY),
+    )
+}
+
+/// Starting z and the constant c for a point of the view, which is c itself for the Mandelbrot
+/// set and z₀ for a Julia set.
+fn starting_point<T: FloatCore>(
+    point: Complex<T>,
+    options: &RenderOptions,
+) -> (Complex<T>, Complex<T>) {
+    match options.julia {
+        Some(julia) => (
+            point,
+            Complex::new(
+                T::from(julia.re).unwrap_or(T::infinity()),
+                T::from(julia.im).unwrap_or(T::infinity()),
+            ),
+        ),
+        None => (Complex::zero(), point),
+    }
+}
+
+/// Iterates a single point, tracking dz/dc (or dz/dz₀ for Julia sets) for the distance estimate
+/// and normals and the closest approach to an orbit trap when asked to.
+fn iterate<T: FloatCore>(
+    point: Complex<T>,
+    max_iterations: u32,
+    options: &RenderOptions,
+    pixel_size: f64,
+) -> SampleOutput {
+    let four = T::from(4).unwrap();
+    let (mut z, c) = starting_point(point, options);
+    // Only the Mandelbrot set's c varies with the point, adding 1 to every derivative step
+    let (mut derivative, increment) = match options.julia {
+        Some(_) => (Complex::<T>::new(T::one(), T::zero()), T::zero()),
+        None => (Complex::<T>::zero(), T::one()),
+    };
+    let mut iteration = 0;
+    let mut trap = TrapHit::default();
+    while iteration < max_iterations && z.norm_sqr() < four {
+        if options.tracks_derivative() {
+            derivative = z * derivative * T::from(2).unwrap() + increment;
+        }
+        z = z * z + c;
+        iteration += 1;
+        update_trap(&mut trap, options, to_f64(z), iteration);
+    }
+    let mut output = SampleOutput {
+        iterations: iteration,
+        trap,
+        ..Default::default()
+    };
+    if iteration < max_iterations && options.tracks_derivative() {
+        let (z, derivative) = (to_f64(z), to_f64(derivative));
+        if options.distance_estimation {
+            let z_norm = z.norm();
+            output.distance = (z_norm * z_norm.ln() / derivative.norm() / pixel_size) as f32;
+        }
+        if options.normals {
+            // The potential's gradient points along z / dz
+            let normal = z / derivative;
+            let normal = normal / normal.norm();
+            output.normal = [normal.re as f32, normal.im as f32];
+        }
+    }
+    output
+}
+
+/// Iterates a single point with a user-defined formula. Without a derivative there is no distance
+/// estimate or normal, but orbit traps still apply.
+fn iterate_formula(
+    point: Complex<f64>,
+    max_iterations: u32,
+    options: &RenderOptions,
+    formula: &CompiledFormula,
+) -> SampleOutput {
+    let (mut z, c) = starting_point(point, options);
+    let mut iteration = 0;
+    let mut trap = TrapHit::default();
+    while iteration < max_iterations && z.norm_sqr() < 4.0 {
+        z = formula.evaluate(z, c);
+        iteration += 1;
+        update_trap(&mut trap, options, z, iteration);
+    }
+    SampleOutput {
+        iterations: iteration,
+        trap,
+        ..Default::default()
+    }
+}
+
+fn update_trap(trap: &mut TrapHit, options: &RenderOptions, z: Complex<f64>, iteration: u32) {
+    if let Some(orbit_trap) = &options.orbit_trap {
+        let distance = orbit_trap.distance(z) as f32;
+        if distance < trap.distance {
+            *trap = TrapHit {
+                distance,
+                iteration,
+            };
+        }
+    }
+}
+
 impl<T: FloatCore + Send + Sync> Renderer<T> for ScalarCpuRenderer {
     type Error = !;
 
@@ -65,7 +206,12 @@ impl<T: FloatCore + Send + Sync> Renderer<T> for ScalarCpuRenderer {
         center: Complex<T>,
         horizontal_radius: T,
         max_iterations: u32,
+        options: &RenderOptions,
     ) -> Result<IterationImage, Self::Error> {
+        let options = &match self.formula {
+            Some(_) => options.without_derivative(),
+            None => *options,
+        };
         let width_float = T::from(self.width.get()).unwrap_or(T::infinity());
         let height_float = T::from(self.height.get()).unwrap_or(T::infinity());
         let vertical_radius = horizontal_radius * height_float / width_float;
@@ -73,57 +219,140 @@ impl<T: FloatCore + Send + Sync> Renderer<T> for ScalarCpuRenderer {
         let max_offset = Complex::new(horizontal_radius, vertical_radius);
         let bottom_left = center - max_offset;
         let top_right = center + max_offset;
+        // Distances are measured in the transformed plane, where pixels cover more or less of it
+        let pixel_size = 2.0 * horizontal_radius.to_f64().unwrap_or(f64::INFINITY)
+            / self.width.get() as f64
+            * options.transform.scale();
+
+        let pixel_to_complex = compose(
+            |(pixel, (offset_x, offset_y)): (usize, (f64, f64))| {
+                (
+                    (pixel % self.width) as f64 + offset_x,
+                    (self.height.get() - pixel / self.width - 1) as f64 + offset_y,
+                )
+            },
+            |(x, y)| {
+                Complex::new(
+                    map(
+                        T::from(x).unwrap_or(T::infinity()),
+                        T::zero(),
+                        width_float,
+                        bottom_left.re,
+                        top_right.re,
+                    ),
+                    map(
+                        T::from(y).unwrap_or(T::infinity()),
+                        T::zero(),
+                        height_float,
+                        bottom_left.im,
+                        top_right.im,
+                    ),
+                )
+            },
+        );
+        let sample = |pixel, offset| {
+            let point = options
+                .transform
+                .transform_point(pixel_to_complex((pixel, offset)), center);
+            match &self.formula {
+                Some(formula) => iterate_formula(to_f64(point), max_iterations, options, formula),
+                None => iterate(point, max_iterations, options, pixel_size),
+            }
+        };
+
+        let supersampling = options.supersampling;
+        let samples_per_pixel = supersampling.samples_per_pixel();
+        let pixel_count = self.width.get() * self.height.get();
 
-        let four = T::from(4).unwrap();
-        self.iteration_image_buffer
-            .par_iter_mut()
+        // A single sample per pixel decides where supersampling is worth it
+        let single_sample_pass = supersampling.is_adaptive().then(|| {
+            let mut buffer = vec![SampleOutput::default(); pixel_count];
+            buffer
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(pixel, value)| *value = sample(pixel, (0.0, 0.0)));
+            let iterations = buffer
+                .iter()
+                .map(|output| output.iterations)
+                .collect::<Vec<_>>();
+            let distances = options.distance_estimation.then(|| {
+                buffer
+                    .iter()
+                    .map(|output| output.distance)
+                    .collect::<Vec<_>>()
+            });
+            let edges = edge_pixels(
+                &iterations,
+                distances.as_deref(),
+                self.width,
+                max_iterations,
+            );
+            (buffer, edges)
+        });
+
+        // Overflow was checked against the maximum sample count when resizing
+        self.sample_buffer.resize(
+            pixel_count * samples_per_pixel.get(),
+            SampleOutput::default(),
+        );
+        self.sample_buffer
+            .par_chunks_mut(samples_per_pixel.get())
             .enumerate()
-            .map(compose(
-                |(i, pixel)| {
-                    (
-                        i % self.width,
-                        self.height.get() - i / self.width - 1,
-                        pixel,
-                    )
-                },
-                |(x, y, pixel)| {
-                    (
-                        Complex::new(
-                            map(
-                                T::from(x).unwrap_or(T::infinity()),
-                                T::zero(),
-                                width_float,
-                                bottom_left.re,
-                                top_right.re,
-                            ),
-                            map(
-                                T::from(y).unwrap_or(T::infinity()),
-                                T::zero(),
-                                height_float,
-                                bottom_left.im,
-                                top_right.im,
-                            ),
-                        ),
-                        pixel,
-                    )
-                },
-            ))
-            .for_each(|(c, pixel)| {
-                let mut z = Complex::<T>::zero();
-                let mut iteration = 0;
-                while iteration < max_iterations && z.norm_sqr() < four {
-                    z = z * z + c;
-                    iteration += 1;
+            .for_each(|(pixel, samples)| match &single_sample_pass {
+                Some((buffer, edges)) if !edges[pixel] => samples.fill(buffer[pixel]),
+                _ => {
+                    for (index, value) in samples.iter_mut().enumerate() {
+                        *value = sample(pixel, supersampling.sample_offset(pixel, index));
+                    }
                 }
-                *pixel = iteration;
             });
 
         // Buffer size was already calculated
-        Ok(IterationImage::from_buffer(
-            self.iteration_image_buffer.clone(),
+        let image = IterationImage::from_buffer(
+            self.sample_buffer
+                .par_iter()
+                .map(|output| output.iterations)
+                .collect(),
             self.width,
+            samples_per_pixel,
             max_iterations,
         )
-        .unwrap())
+        .unwrap();
+        let image = if options.distance_estimation {
+            image
+                .with_distances(
+                    self.sample_buffer
+                        .par_iter()
+                        .map(|output| output.distance)
+                        .collect(),
+                )
+                .unwrap()
+        } else {
+            image
+        };
+        let image = if options.normals {
+            image
+                .with_normals(
+                    self.sample_buffer
+                        .par_iter()
+                        .map(|output| output.normal)
+                        .collect(),
+                )
+                .unwrap()
+        } else {
+            image
+        };
+        Ok(if options.orbit_trap.is_some() {
+            image
+                .with_trap_hits(
+                    self.sample_buffer
+                        .par_iter()
+                        .map(|output| output.trap)
+                        .collect(),
+                )
+                .unwrap()
+        } else {
+            image
+        })
     }
 }
@@ -0,0 +1,119 @@
+use std::{
+    num::NonZeroUsize,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use num::Complex;
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+use crate::{density_image::DensityImage, error::IntegerOverflow, Dimensions};
+
+use super::{complex_to_pixel, sample_point, Accumulation, DensityParameters, DensityRenderer};
+
+pub struct CpuDensityRenderer {
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+    /// Red, green and blue visit counts for every pixel, saturating instead of wrapping around.
+    histogram: Vec<AtomicU32>,
+    samples: u64,
+    accumulation: Option<Accumulation>,
+}
+
+fn histogram_length(width: NonZeroUsize, height: NonZeroUsize) -> Result<usize, IntegerOverflow> {
+    width
+        .checked_mul(height)
+        .and_then(|pixel_count| pixel_count.checked_mul(NonZeroUsize::new(3).unwrap()))
+        .map(NonZeroUsize::get)
+        .ok_or(IntegerOverflow)
+}
+
+impl CpuDensityRenderer {
+    pub fn new(width: NonZeroUsize, height: NonZeroUsize) -> Result<Self, IntegerOverflow> {
+        Ok(Self {
+            width,
+            height,
+            histogram: (0..histogram_length(width, height)?)
+                .map(|_| AtomicU32::new(0))
+                .collect(),
+            samples: 0,
+            accumulation: None,
+        })
+    }
+
+    pub fn resize(
+        &mut self,
+        Dimensions { width, height }: Dimensions,
+    ) -> Result<(), IntegerOverflow> {
+        *self = Self::new(width, height)?;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        for count in &mut self.histogram {
+            *count.get_mut() = 0;
+        }
+        self.samples = 0;
+    }
+}
+
+impl DensityRenderer for CpuDensityRenderer {
+    type Error = !;
+
+    fn accumulate(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        parameters: &DensityParameters,
+        samples: u64,
+    ) -> Result<DensityImage, Self::Error> {
+        let accumulation = Accumulation {
+            center,
+            horizontal_radius,
+            parameters: *parameters,
+        };
+        if self.accumulation != Some(accumulation) {
+            self.reset();
+            self.accumulation = Some(accumulation);
+        }
+
+        let (width, height) = (self.width.get(), self.height.get());
+        let histogram = &self.histogram;
+        (self.samples..self.samples + samples)
+            .into_par_iter()
+            .for_each(|sample| {
+                let c = sample_point(sample);
+                let orbit_lengths = parameters.orbit_lengths(c);
+                let longest = orbit_lengths.into_iter().max().unwrap_or(0);
+                let mut z = Complex::new(0.0, 0.0);
+                for iteration in 1..=longest {
+                    z = z * z + c;
+                    let Some(pixel) = complex_to_pixel(z, center, horizontal_radius, width, height)
+                    else {
+                        continue;
+                    };
+                    for (channel, &length) in orbit_lengths.iter().enumerate() {
+                        if iteration <= length {
+                            // A full count has nothing left to update, which is not an error
+                            let _ = histogram[pixel * 3 + channel].fetch_update(
+                                Ordering::Relaxed,
+                                Ordering::Relaxed,
+                                |count| count.checked_add(1),
+                            );
+                        }
+                    }
+                }
+            });
+        self.samples += samples;
+
+        // Buffer size was already calculated
+        Ok(DensityImage::from_buffer(
+            histogram
+                .par_iter()
+                .map(|count| count.load(Ordering::Relaxed))
+                .collect(),
+            self.width,
+            self.samples,
+        )
+        .unwrap())
+    }
+}
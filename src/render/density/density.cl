@@ -0,0 +1,97 @@
+// Must match `random` on the host
+double random(ulong sample, ulong draw) {
+    ulong z = (sample * 2 + draw + 1) * 0x9e3779b97f4a7c15ul;
+    z = (z ^ (z >> 30)) * 0xbf58476d1ce4e5b9ul;
+    z = (z ^ (z >> 27)) * 0x94d049bb133111ebul;
+    z ^= z >> 31;
+    return (double) (z >> 11) / 9007199254740992.0;
+}
+
+bool in_cardioid_or_bulb(double2 c) {
+    const double q = (c.x - 0.25) * (c.x - 0.25) + c.y * c.y;
+    return q * (q + (c.x - 0.25)) <= 0.25 * c.y * c.y || (c.x + 1.0) * (c.x + 1.0) + c.y * c.y <= 0.0625;
+}
+
+// `escaped_after` is 0 for orbits that never escaped
+uint channel_length(uint anti, uint escaped_after, uint limit) {
+    if (anti) {
+        return escaped_after == 0 || escaped_after > limit ? limit : 0;
+    }
+    return escaped_after != 0 && escaped_after <= limit ? escaped_after : 0;
+}
+
+// Must match `DensityParameters::orbit_lengths` on the host
+uint3 orbit_lengths(double2 c, uint anti, uint3 limits) {
+    if (!anti && in_cardioid_or_bulb(c)) {
+        return (uint3) (0, 0, 0);
+    }
+    const uint max_iterations = max(limits.x, max(limits.y, limits.z));
+    double2 z = (double2) (0.0, 0.0);
+    uint escaped_after = 0;
+    for (uint iteration = 1; iteration <= max_iterations; iteration++) {
+        z = (double2) (z.x * z.x - z.y * z.y + c.x, 2.0 * z.x * z.y + c.y);
+        if (z.x * z.x + z.y * z.y > 4.0) {
+            escaped_after = iteration;
+            break;
+        }
+    }
+    return (uint3) (
+        channel_length(anti, escaped_after, limits.x),
+        channel_length(anti, escaped_after, limits.y),
+        channel_length(anti, escaped_after, limits.z)
+    );
+}
+
+// Must match `complex_to_pixel` on the host, returning -1 outside the image
+long complex_to_pixel(double2 z, double center_re, double center_im, double radius, ulong width, ulong height) {
+    const double vertical_radius = radius * (double) height / (double) width;
+    const double x = floor((z.x - center_re + radius) / (2.0 * radius) * (double) width);
+    const double y = floor((z.y - center_im + vertical_radius) / (2.0 * vertical_radius) * (double) height);
+    if (x < 0.0 || x >= (double) width || y < 0.0 || y >= (double) height) {
+        return -1;
+    }
+    return (long) ((height - 1 - (ulong) y) * width + (ulong) x);
+}
+
+// Counts a visit unless the count is already full, so the most visited pixels stay the brightest
+// however long the image accumulates. Must match the `checked_add` on the host.
+void saturating_inc(global volatile uint* count) {
+    uint old = *count;
+    while (old != UINT_MAX) {
+        const uint seen = atomic_cmpxchg(count, old, old + 1);
+        if (seen == old) {
+            return;
+        }
+        old = seen;
+    }
+}
+
+// Traces one orbit per work item, counting its visits to every pixel in the channels it qualifies for
+kernel void density(ulong first_sample, double center_re, double center_im, double radius, ulong width, ulong height, uint anti, uint red_limit, uint green_limit, uint blue_limit, global volatile uint* histogram) {
+    const ulong sample = first_sample + get_global_id(0);
+    // Sampled from the same square as `sample_point` on the host
+    const double2 c = (double2) (
+        (random(sample, 0) * 2.0 - 1.0) * 2.0,
+        (random(sample, 1) * 2.0 - 1.0) * 2.0
+    );
+    const uint3 lengths = orbit_lengths(c, anti, (uint3) (red_limit, green_limit, blue_limit));
+    const uint longest = max(lengths.x, max(lengths.y, lengths.z));
+
+    double2 z = (double2) (0.0, 0.0);
+    for (uint iteration = 1; iteration <= longest; iteration++) {
+        z = (double2) (z.x * z.x - z.y * z.y + c.x, 2.0 * z.x * z.y + c.y);
+        const long pixel = complex_to_pixel(z, center_re, center_im, radius, width, height);
+        if (pixel < 0) {
+            continue;
+        }
+        if (iteration <= lengths.x) {
+            saturating_inc(&histogram[pixel * 3]);
+        }
+        if (iteration <= lengths.y) {
+            saturating_inc(&histogram[pixel * 3 + 1]);
+        }
+        if (iteration <= lengths.z) {
+            saturating_inc(&histogram[pixel * 3 + 2]);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use num::Complex;
+
+use crate::density_image::DensityImage;
+
+pub mod cpu;
+pub mod opencl;
+
+/// Half the side of the square around the origin that orbits are started from.
+const SAMPLING_RADIUS: f64 = 2.0;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum DensityMode {
+    /// Accumulates orbits that escape within the iteration limit.
+    Buddhabrot,
+    /// Accumulates orbits that do not escape within the iteration limit.
+    AntiBuddhabrot,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct DensityParameters {
+    pub mode: DensityMode,
+    /// Iteration limits of the red, green and blue channels.
+    pub iteration_limits: [u32; 3],
+}
+
+impl DensityParameters {
+    pub fn buddhabrot(max_iterations: u32) -> Self {
+        Self {
+            mode: DensityMode::Buddhabrot,
+            iteration_limits: [max_iterations; 3],
+        }
+    }
+
+    /// A Buddhabrot with a tenth and a hundredth of the iteration limit in green and blue.
+    pub fn nebulabrot(max_iterations: u32) -> Self {
+        Self {
+            mode: DensityMode::Buddhabrot,
+            iteration_limits: [1, 10, 100].map(|divisor| (max_iterations / divisor).max(1)),
+        }
+    }
+
+    pub fn anti_buddhabrot(max_iterations: u32) -> Self {
+        Self {
+            mode: DensityMode::AntiBuddhabrot,
+            iteration_limits: [max_iterations; 3],
+        }
+    }
+
+    fn max_iterations(&self) -> u32 {
+        self.iteration_limits.into_iter().max().unwrap_or(0)
+    }
+
+    /// How many points of the orbit of `c` each channel records, identical to `orbit_lengths` in
+    /// `density.cl`.
+    fn orbit_lengths(&self, c: Complex<f64>) -> [u32; 3] {
+        // Neither the main cardioid nor the period 2 bulb ever escape
+        if self.mode == DensityMode::Buddhabrot && in_cardioid_or_bulb(c) {
+            return [0; 3];
+        }
+        let mut z = Complex::new(0.0, 0.0);
+        let mut escaped_after = None;
+        for iteration in 1..=self.max_iterations() {
+            z = z * z + c;
+            if z.norm_sqr() > 4.0 {
+                escaped_after = Some(iteration);
+                break;
+            }
+        }
+        self.iteration_limits
+            .map(|limit| match (self.mode, escaped_after) {
+                (DensityMode::Buddhabrot, Some(iterations)) if iterations <= limit => iterations,
+                (DensityMode::AntiBuddhabrot, None) => limit,
+                (DensityMode::AntiBuddhabrot, Some(iterations)) if iterations > limit => limit,
+                _ => 0,
+            })
+    }
+}
+
+impl std::fmt::Display for DensityParameters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [red, green, blue] = self.iteration_limits;
+        match self.mode {
+            DensityMode::Buddhabrot if red == green && green == blue => write!(f, "Buddhabrot"),
+            DensityMode::Buddhabrot => write!(f, "Nebulabrot ({red}/{green}/{blue})"),
+            DensityMode::AntiBuddhabrot => write!(f, "Anti-Buddhabrot"),
+        }
+    }
+}
+
+fn in_cardioid_or_bulb(c: Complex<f64>) -> bool {
+    let q = (c.re - 0.25).powi(2) + c.im * c.im;
+    q * (q + (c.re - 0.25)) <= 0.25 * c.im * c.im || (c.re + 1.0).powi(2) + c.im * c.im <= 0.0625
+}
+
+/// Uniform random number from 0 to 1 for the `draw`th draw of the `sample`th sample, identical to
+/// `random` in `density.cl` so both renderers sample the same orbits.
+fn random(sample: u64, draw: u64) -> f64 {
+    let mut z = (sample * 2 + draw)
+        .wrapping_add(1)
+        .wrapping_mul(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+fn sample_point(sample: u64) -> Complex<f64> {
+    Complex::new(
+        (random(sample, 0) * 2.0 - 1.0) * SAMPLING_RADIUS,
+        (random(sample, 1) * 2.0 - 1.0) * SAMPLING_RADIUS,
+    )
+}
+
+/// The view an accumulated image belongs to; changing any of it starts the image over.
+#[derive(PartialEq, Clone, Copy, Debug)]
+struct Accumulation {
+    center: Complex<f64>,
+    horizontal_radius: f64,
+    parameters: DensityParameters,
+}
+
+pub trait DensityRenderer {
+    type Error;
+
+    /// Samples `samples` more orbits into the image, starting it over if the view or parameters
+    /// changed since the last call. Samples are numbered from the start of the image and seed the
+    /// random numbers, so the result does not depend on how it was split into calls.
+    fn accumulate(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        parameters: &DensityParameters,
+        samples: u64,
+    ) -> Result<DensityImage, Self::Error>;
+}
+
+/// Pixel containing `z`, the inverse of `pixel_to_complex` without sample offsets. Identical to
+/// `complex_to_pixel` in `density.cl`.
+fn complex_to_pixel(
+    z: Complex<f64>,
+    center: Complex<f64>,
+    horizontal_radius: f64,
+    width: usize,
+    height: usize,
+) -> Option<usize> {
+    let vertical_radius = horizontal_radius * height as f64 / width as f64;
+    let x =
+        ((z.re - center.re + horizontal_radius) / (2.0 * horizontal_radius) * width as f64).floor();
+    let y =
+        ((z.im - center.im + vertical_radius) / (2.0 * vertical_radius) * height as f64).floor();
+    if !(0.0..width as f64).contains(&x) || !(0.0..height as f64).contains(&y) {
+        return None;
+    }
+    Some((height - 1 - y as usize) * width + x as usize)
+}
@@ -0,0 +1,149 @@
+use std::{num::NonZeroUsize, ptr};
+
+use num::Complex;
+use opencl3::{
+    command_queue::CommandQueue,
+    context::Context,
+    error_codes::ClError,
+    kernel::{ExecuteKernel, Kernel},
+    memory::Buffer,
+    program::Program,
+    types::CL_NON_BLOCKING,
+};
+
+use crate::{density_image::DensityImage, error::OpenclError, Dimensions};
+
+use super::{
+    super::opencl::default_device, Accumulation, DensityMode, DensityParameters, DensityRenderer,
+};
+
+static KERNEL_SOURCE: &str = include_str!("density.cl");
+
+pub struct OpenclDensityRenderer {
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+    context: Context,
+    queue: CommandQueue,
+    kernel: Kernel,
+    /// Red, green and blue visit counts for every pixel, saturating instead of wrapping around.
+    histogram: Buffer<u32>,
+    samples: u64,
+    accumulation: Option<Accumulation>,
+}
+
+fn create_histogram(
+    context: &Context,
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+) -> Result<Buffer<u32>, OpenclError> {
+    let length = width
+        .checked_mul(height)
+        .and_then(|pixel_count| pixel_count.checked_mul(NonZeroUsize::new(3).unwrap()))
+        .ok_or(OpenclError::IntegerOverflow)?;
+    Ok(unsafe { Buffer::create(context, 0, length.get(), ptr::null_mut()) }?)
+}
+
+impl OpenclDensityRenderer {
+    pub fn new(width: NonZeroUsize, height: NonZeroUsize) -> Result<Self, OpenclError> {
+        let device = default_device()?;
+        let context = Context::from_device(&device)?;
+        let queue = CommandQueue::create_default_with_properties(&context, 0, 0)?;
+        let program = Program::create_and_build_from_source(&context, KERNEL_SOURCE, "")?;
+        let kernel = Kernel::create(&program, "density")?;
+        let histogram = create_histogram(&context, width, height)?;
+        Ok(Self {
+            width,
+            height,
+            context,
+            queue,
+            kernel,
+            histogram,
+            samples: 0,
+            // Forces the histogram to be cleared before the first batch
+            accumulation: None,
+        })
+    }
+
+    pub fn resize(&mut self, Dimensions { width, height }: Dimensions) -> Result<(), OpenclError> {
+        self.histogram = create_histogram(&self.context, width, height)?;
+        self.width = width;
+        self.height = height;
+        self.accumulation = None;
+        Ok(())
+    }
+
+    fn histogram_length(&self) -> usize {
+        self.width.get() * self.height.get() * 3
+    }
+
+    fn reset(&mut self) -> Result<(), ClError> {
+        let length = self.histogram_length();
+        unsafe {
+            self.queue.enqueue_fill_buffer(
+                &mut self.histogram,
+                &[0],
+                0,
+                length * size_of::<u32>(),
+                &[],
+            )
+        }?
+        .wait()?;
+        self.samples = 0;
+        Ok(())
+    }
+}
+
+impl DensityRenderer for OpenclDensityRenderer {
+    type Error = ClError;
+
+    fn accumulate(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        parameters: &DensityParameters,
+        samples: u64,
+    ) -> Result<DensityImage, Self::Error> {
+        let accumulation = Accumulation {
+            center,
+            horizontal_radius,
+            parameters: *parameters,
+        };
+        if self.accumulation != Some(accumulation) {
+            self.reset()?;
+            self.accumulation = Some(accumulation);
+        }
+
+        let [red_limit, green_limit, blue_limit] = parameters.iteration_limits;
+        let kernel_event = unsafe {
+            ExecuteKernel::new(&self.kernel)
+                .set_arg(&self.samples)
+                .set_arg(&center.re)
+                .set_arg(&center.im)
+                .set_arg(&horizontal_radius)
+                .set_arg(&(self.width.get() as u64))
+                .set_arg(&(self.height.get() as u64))
+                .set_arg(&u32::from(parameters.mode == DensityMode::AntiBuddhabrot))
+                .set_arg(&red_limit)
+                .set_arg(&green_limit)
+                .set_arg(&blue_limit)
+                .set_arg(&self.histogram)
+                .set_global_work_size(samples as usize)
+                .enqueue_nd_range(&self.queue)
+        }?;
+        self.samples += samples;
+
+        let mut histogram = vec![0; self.histogram_length()];
+        let read_buffer_event = unsafe {
+            self.queue.enqueue_read_buffer(
+                &self.histogram,
+                CL_NON_BLOCKING,
+                0,
+                &mut histogram,
+                &[kernel_event.get()],
+            )
+        }?;
+        read_buffer_event.wait()?;
+        // Buffer size was already calculated
+        Ok(DensityImage::from_buffer(histogram, self.width, self.samples).unwrap())
+    }
+}
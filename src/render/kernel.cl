@@ -1,39 +1,186 @@
-double lerp_inverse(double t, double min, double max) {
-    return (t - min) / (max - min);
+uint hash(uint x) {
+    x ^= x >> 16;
+    x *= 0x7feb352du;
+    x ^= x >> 15;
+    x *= 0x846ca68bu;
+    x ^= x >> 16;
+    return x;
 }
 
-double map(double value, double from_source, double to_source, double from_target, double to_target) {
-    return mix(from_target, to_target, lerp_inverse(value, from_source, to_source));
+double unit_random(uint seed) {
+    return (double) hash(seed) / 4294967296.0;
 }
 
-kernel void render(ulong width, double center_re, double center_im, double radius, uint max_iterations, global uint* output) {
+// Must match `Supersampling::sample_offset` on the host
+double2 sample_offset(ulong pixel, uint sample, uint samples_per_pixel, global const double* offsets, double jitter) {
+    const uint seed = (uint) pixel * (samples_per_pixel * 2) + sample * 2;
+    return (double2) (
+        offsets[sample * 2] + (unit_random(seed) - 0.5) * jitter,
+        offsets[sample * 2 + 1] + (unit_random(seed + 1) - 0.5) * jitter
+    );
+}
+
+// Laid out like `TrapHit` on the host
+typedef struct {
+    float distance;
+    uint iteration;
+} trap_hit;
+
+// Must match `OrbitTrap::distance` on the host, with shapes numbered as in `OrbitTrap::kernel_arguments`
+double orbit_trap_distance(uint shape, double4 parameters, double2 z) {
+    const double2 offset = z - parameters.xy;
+    switch (shape) {
+        case 1:
+            return length(offset);
+        case 2:
+            return fmin(fabs(offset.x), fabs(offset.y));
+        case 3:
+            return fabs(length(offset) - parameters.z);
+        case 4:
+            return fabs(offset.y * cos(parameters.z) - offset.x * sin(parameters.z));
+        case 5: {
+            const double distance = fmin(fabs(z.x), fabs(z.y));
+            return distance < parameters.z ? distance / parameters.z : INFINITY;
+        }
+        default:
+            return INFINITY;
+    }
+}
+
+// Iterates a single point with `next_z`, tracking dz/dc (or dz/dz₀ for Julia sets) for the
+// distance estimate and normals and the closest approach to an orbit trap when asked to. The
+// derivative assumes z² + c, so the host never asks for it with a formula. Like `iterate` on the
+// host, the point is z₀ rather than c for Julia sets.
+uint iterate(double2 point, uint max_iterations, uint distance_estimation, uint normals, uint trap_shape, double4 trap_parameters, uint julia, double2 julia_c, double pixel_size, float* distance, float2* normal, trap_hit* trap) {
+    const bool tracks_derivative = distance_estimation || normals;
+    const double2 c = julia ? julia_c : point;
+    double z_re = julia ? point.x : 0.0;
+    double z_im = julia ? point.y : 0.0;
+    double derivative_re = julia ? 1.0 : 0.0;
+    double derivative_im = 0.0;
+    // Only the Mandelbrot set's c varies with the point, adding 1 to every derivative step
+    const double increment = julia ? 0.0 : 1.0;
+    trap->distance = INFINITY;
+    trap->iteration = 0;
+    uint iteration;
+    for (iteration = 0; iteration < max_iterations && z_re * z_re + z_im * z_im < 4.0; iteration++) {
+        if (tracks_derivative) {
+            const double derivative_re_old = derivative_re;
+            derivative_re = 2.0 * (z_re * derivative_re - z_im * derivative_im) + increment;
+            derivative_im = 2.0 * (z_re * derivative_im + z_im * derivative_re_old);
+        }
+        const double2 z = next_z((double2) (z_re, z_im), c);
+        z_re = z.x;
+        z_im = z.y;
+        if (trap_shape) {
+            const float trap_distance = (float) orbit_trap_distance(trap_shape, trap_parameters, z);
+            if (trap_distance < trap->distance) {
+                trap->distance = trap_distance;
+                trap->iteration = iteration + 1;
+            }
+        }
+    }
+    *distance = 0.0f;
+    *normal = (float2) (0.0f, 0.0f);
+    if (iteration < max_iterations && distance_estimation) {
+        const double z_norm = sqrt(z_re * z_re + z_im * z_im);
+        const double derivative_norm = sqrt(derivative_re * derivative_re + derivative_im * derivative_im);
+        *distance = (float) (z_norm * log(z_norm) / derivative_norm / pixel_size);
+    }
+    if (iteration < max_iterations && normals) {
+        // The potential's gradient points along z / dz, i.e. along z * conj(dz)
+        const double2 direction = (double2) (
+            z_re * derivative_re + z_im * derivative_im,
+            z_im * derivative_re - z_re * derivative_im
+        );
+        *normal = convert_float2(normalize(direction));
+    }
+    return iteration;
+}
+
+void render_sample(ulong width, ulong height, double center_re, double center_im, double radius, uint max_iterations, global const double* offsets, uint samples_per_pixel, double jitter, uint distance_estimation, uint normals, uint trap_shape, double4 trap_parameters, uint julia, double2 julia_c, double4 transform, ulong pixel, uint sample, size_t output_index, global uint* output, global float* distances, global float2* normal_output, global trap_hit* trap_output) {
+    const double2 offset = sample_offset(pixel, sample, samples_per_pixel, offsets, jitter);
+    const double2 c = transform_point(pixel_to_complex(width, height, center_re, center_im, radius, pixel, offset), (double2) (center_re, center_im), transform);
+    const double pixel_size = 2.0 * radius / (double) width * transform_scale(transform);
+
+    float distance;
+    float2 normal;
+    trap_hit trap;
+    output[output_index] = iterate(c, max_iterations, distance_estimation, normals, trap_shape, trap_parameters, julia, julia_c, pixel_size, &distance, &normal, &trap);
+    if (distance_estimation) {
+        distances[output_index] = distance;
+    }
+    if (normals) {
+        normal_output[output_index] = normal;
+    }
+    if (trap_shape) {
+        trap_output[output_index] = trap;
+    }
+}
+
+kernel void render(ulong width, ulong height, double center_re, double center_im, double radius, uint max_iterations, global const double* offsets, uint samples_per_pixel, double jitter, uint distance_estimation, uint normals, uint trap_shape, double4 trap_parameters, uint julia, double2 julia_c, double4 transform, global uint* output, global float* distances, global float2* normal_output, global trap_hit* trap_output) {
+    const size_t global_id = get_global_id(0);
+    const ulong pixel = global_id / samples_per_pixel;
+    const uint sample = global_id % samples_per_pixel;
+
+    render_sample(width, height, center_re, center_im, radius, max_iterations, offsets, samples_per_pixel, jitter, distance_estimation, normals, trap_shape, trap_parameters, julia, julia_c, transform, pixel, sample, global_id, output, distances, normal_output, trap_output);
+}
+
+// Supersamples only the listed pixels, writing their samples consecutively
+kernel void render_pixels(ulong width, ulong height, double center_re, double center_im, double radius, uint max_iterations, global const double* offsets, uint samples_per_pixel, double jitter, uint distance_estimation, uint normals, uint trap_shape, double4 trap_parameters, uint julia, double2 julia_c, double4 transform, global const ulong* pixels, global uint* output, global float* distances, global float2* normal_output, global trap_hit* trap_output) {
     const size_t global_id = get_global_id(0);
-    const size_t global_size = get_global_size(0);
+    const ulong pixel = pixels[global_id / samples_per_pixel];
+    const uint sample = global_id % samples_per_pixel;
 
-    const ulong height = global_size / width;
-    const double width_double = (double) width;    
-    const double height_double = (double) height;
+    render_sample(width, height, center_re, center_im, radius, max_iterations, offsets, samples_per_pixel, jitter, distance_estimation, normals, trap_shape, trap_parameters, julia, julia_c, transform, pixel, sample, global_id, output, distances, normal_output, trap_output);
+}
 
-    const double max_offset_re = radius;
-    const double max_offset_im = radius * height_double / width_double;
+// Must match `Light::brightness` on the host, with `light` holding azimuth, elevation and height
+double brightness(double4 light, float2 normal) {
+    const double3 surface_normal = (double3) ((double) normal.x, (double) normal.y, light.z);
+    const double3 light_direction = (double3) (
+        cos(light.y) * cos(light.x),
+        cos(light.y) * sin(light.x),
+        sin(light.y)
+    );
+    return fmax(dot(surface_normal, light_direction) / length(surface_normal), 0.0);
+}
 
-    const double bottom_left_re = center_re - max_offset_re;
-    const double bottom_left_im = center_im - max_offset_im;
-    const double top_right_re = center_re + max_offset_re;
-    const double top_right_im = center_im + max_offset_im;
+// Applies the palette to every pixel's samples and averages the colours, like
+// `IterationImage::write_to_texture` with `Colouring::colour`. Shadings are numbered as in
+// `OpenclRenderer::colour`, and the channels a shading needs fall back to escape time if missing.
+kernel void colour(global const uint* iterations, global const float* distances, global const float2* normals, global const trap_hit* traps, uint has_distances, uint has_normals, uint has_traps, uint samples_per_pixel, uint max_iterations, uint shading, double4 light, double distance_falloff, double trap_falloff, double trap_iteration_step, global const uchar* palette, uint palette_length, double cycle_offset, global uchar* output) {
+    const size_t pixel = get_global_id(0);
+    const uint offset_entries = (uint) (cycle_offset * palette_length);
 
-    const ulong x = global_id % width, y = height - global_id / width - 1;
+    uint3 sum = (uint3) (0, 0, 0);
+    for (uint sample = 0; sample < samples_per_pixel; sample++) {
+        const size_t index = pixel * samples_per_pixel + sample;
+        const uint iteration = iterations[index];
+        const bool escaped = iteration < max_iterations;
 
-    const double c_re = map(x, 0.0, width_double, bottom_left_re, top_right_re);
-    const double c_im = map(y, 0.0, height_double, bottom_left_im, top_right_im);
+        double t = (double) iteration / (double) max_iterations;
+        double brightness = 1.0;
+        if (shading == 1 && has_distances) {
+            t = 1.0 - exp(-(double) distances[index] / distance_falloff);
+        } else if (shading == 2 && has_normals) {
+            t = escaped ? brightness(light, normals[index]) : 0.0;
+        } else if (shading == 3 && has_traps) {
+            t = fmod((double) traps[index].iteration * trap_iteration_step, 1.0);
+            brightness = exp(-(double) traps[index].distance / trap_falloff);
+        }
 
-    double z_re = 0.0;
-    double z_im = 0.0;
-    uint iteration;
-    for (iteration = 0; iteration < max_iterations && z_re * z_re + z_im * z_im < 4.0; iteration++) {
-        const double z_re_old = z_re;
-        z_re = z_re * z_re - z_im * z_im + c_re;
-        z_im = 2.0 * z_re_old * z_im + c_im;
+        const uint entry = (uint) (clamp(t, 0.0, 1.0) * (palette_length - 1));
+        const uint rotated = (entry + (escaped ? offset_entries : 0)) % palette_length;
+        sum += (uint3) (
+            (uint) ((double) palette[rotated * 3] * brightness),
+            (uint) ((double) palette[rotated * 3 + 1] * brightness),
+            (uint) ((double) palette[rotated * 3 + 2] * brightness)
+        );
     }
-    output[global_id] = iteration;
+
+    const uint3 average = (sum + samples_per_pixel / 2) / samples_per_pixel;
+    output[pixel * 3] = average.x;
+    output[pixel * 3 + 1] = average.y;
+    output[pixel * 3 + 2] = average.z;
 }
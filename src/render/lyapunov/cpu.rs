@@ -0,0 +1,51 @@
+use std::num::NonZeroUsize;
+
+use num::Complex;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{error::IntegerOverflow, lyapunov_image::LyapunovImage, Dimensions};
+
+use super::{super::pixel_to_complex, LyapunovParameters, LyapunovRenderer};
+
+pub struct CpuLyapunovRenderer {
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+}
+
+impl CpuLyapunovRenderer {
+    pub fn new(width: NonZeroUsize, height: NonZeroUsize) -> Result<Self, IntegerOverflow> {
+        width.checked_mul(height).ok_or(IntegerOverflow)?;
+        Ok(Self { width, height })
+    }
+
+    pub fn resize(
+        &mut self,
+        Dimensions { width, height }: Dimensions,
+    ) -> Result<(), IntegerOverflow> {
+        *self = Self::new(width, height)?;
+        Ok(())
+    }
+}
+
+impl LyapunovRenderer for CpuLyapunovRenderer {
+    type Error = !;
+
+    fn render(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        parameters: &LyapunovParameters,
+        max_iterations: u32,
+    ) -> Result<LyapunovImage, Self::Error> {
+        let buffer = (0..self.width.get() * self.height.get())
+            .into_par_iter()
+            .map(|pixel| {
+                let rates =
+                    pixel_to_complex(pixel, center, horizontal_radius, self.width, self.height);
+                parameters.exponent(rates.re, rates.im, max_iterations)
+            })
+            .collect();
+        // Buffer size was already calculated
+        Ok(LyapunovImage::from_buffer(buffer, self.width).unwrap())
+    }
+}
@@ -0,0 +1,27 @@
+// Must match `Sequence::rate` on the host
+double rate(ulong sequence, uint sequence_length, uint n, double a, double b) {
+    return (sequence >> (n % sequence_length)) & 1 ? b : a;
+}
+
+// Must match `LyapunovParameters::exponent` on the host
+float lyapunov(double a, double b, ulong sequence, uint sequence_length, uint warmup, uint iterations) {
+    double x = 0.5;
+    for (uint n = 0; n < warmup; n++) {
+        const double r = rate(sequence, sequence_length, n, a, b);
+        x = r * x * (1.0 - x);
+    }
+    double sum = 0.0;
+    const uint end = warmup + min(iterations, UINT_MAX - warmup);
+    for (uint n = warmup; n < end; n++) {
+        const double r = rate(sequence, sequence_length, n, a, b);
+        sum += log(fabs(r * (1.0 - 2.0 * x)));
+        x = r * x * (1.0 - x);
+    }
+    return (float) (sum / (double) max(iterations, 1u));
+}
+
+kernel void render_lyapunov(ulong width, ulong height, double center_re, double center_im, double radius, ulong sequence, uint sequence_length, uint warmup, uint iterations, global float* output) {
+    const size_t pixel = get_global_id(0);
+    const double2 rates = pixel_to_complex(width, height, center_re, center_im, radius, pixel, (double2) (0.0, 0.0));
+    output[pixel] = lyapunov(rates.x, rates.y, sequence, sequence_length, warmup, iterations);
+}
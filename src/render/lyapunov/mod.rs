@@ -0,0 +1,134 @@
+use std::str::FromStr;
+
+use num::Complex;
+
+use crate::{error::SequenceError, lyapunov_image::LyapunovImage};
+
+pub mod cpu;
+pub mod opencl;
+
+/// Value of the logistic map's state before the first iteration.
+const INITIAL_STATE: f64 = 0.5;
+
+/// The order in which the logistic map alternates between the growth rates `a` and `b`, e.g.
+/// `AABAB`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Sequence {
+    /// Bit `n` is set when the `n`th step uses `b`.
+    bits: u64,
+    length: u32,
+}
+
+impl Sequence {
+    pub const MAX_LENGTH: usize = u64::BITS as usize;
+
+    /// Growth rate of step `n`, repeating the sequence forever.
+    fn rate(&self, n: u32, a: f64, b: f64) -> f64 {
+        if self.bits >> (n % self.length) & 1 == 1 {
+            b
+        } else {
+            a
+        }
+    }
+
+    /// Arguments passed to `lyapunov.cl`.
+    fn kernel_arguments(&self) -> (u64, u32) {
+        (self.bits, self.length)
+    }
+}
+
+impl Default for Sequence {
+    /// `AB`.
+    fn default() -> Self {
+        Self { bits: 2, length: 2 }
+    }
+}
+
+impl FromStr for Sequence {
+    type Err = SequenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(SequenceError::Empty);
+        }
+        if s.chars().count() > Self::MAX_LENGTH {
+            return Err(SequenceError::TooLong);
+        }
+        let mut bits = 0;
+        for (index, letter) in s.chars().enumerate() {
+            match letter.to_ascii_uppercase() {
+                'A' => (),
+                'B' => bits |= 1 << index,
+                _ => return Err(SequenceError::InvalidLetter(letter)),
+            }
+        }
+        Ok(Self {
+            bits,
+            length: s.chars().count() as u32,
+        })
+    }
+}
+
+impl std::fmt::Display for Sequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for n in 0..self.length {
+            write!(f, "{}", if self.bits >> n & 1 == 1 { 'B' } else { 'A' })?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct LyapunovParameters {
+    pub sequence: Sequence,
+    /// Iterations run before the exponent starts being averaged, letting the orbit settle.
+    pub warmup: u32,
+}
+
+impl Default for LyapunovParameters {
+    fn default() -> Self {
+        Self {
+            sequence: Sequence::default(),
+            warmup: 100,
+        }
+    }
+}
+
+impl LyapunovParameters {
+    /// Lyapunov exponent of the logistic map with growth rates `a` and `b`, averaged over
+    /// `iterations` steps. Identical to `lyapunov` in `lyapunov.cl`.
+    fn exponent(&self, a: f64, b: f64, iterations: u32) -> f32 {
+        let mut x = INITIAL_STATE;
+        for n in 0..self.warmup {
+            let r = self.sequence.rate(n, a, b);
+            x = r * x * (1.0 - x);
+        }
+        let mut sum = 0.0;
+        for n in self.warmup..self.warmup.saturating_add(iterations) {
+            let r = self.sequence.rate(n, a, b);
+            sum += (r * (1.0 - 2.0 * x)).abs().ln();
+            x = r * x * (1.0 - x);
+        }
+        (sum / f64::from(iterations.max(1))) as f32
+    }
+}
+
+impl std::fmt::Display for LyapunovParameters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, {} warm-up iterations", self.sequence, self.warmup)
+    }
+}
+
+pub trait LyapunovRenderer {
+    type Error;
+
+    /// Renders the exponent over the plane of growth rates, with `a` along the real axis and `b`
+    /// along the imaginary one.
+    fn render(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        parameters: &LyapunovParameters,
+        max_iterations: u32,
+    ) -> Result<LyapunovImage, Self::Error>;
+}
@@ -0,0 +1,113 @@
+use std::{num::NonZeroUsize, ptr};
+
+use num::Complex;
+use opencl3::{
+    command_queue::CommandQueue,
+    context::Context,
+    error_codes::ClError,
+    kernel::{ExecuteKernel, Kernel},
+    memory::Buffer,
+    program::Program,
+    types::CL_NON_BLOCKING,
+};
+
+use crate::{error::OpenclError, lyapunov_image::LyapunovImage, Dimensions};
+
+use super::{super::opencl::default_device, LyapunovParameters, LyapunovRenderer};
+
+static KERNEL_SOURCE: &str = concat!(
+    include_str!("../common.cl"),
+    "\n",
+    include_str!("lyapunov.cl")
+);
+
+pub struct OpenclLyapunovRenderer {
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+    context: Context,
+    queue: CommandQueue,
+    kernel: Kernel,
+    buffer: Buffer<f32>,
+}
+
+fn create_output_buffer(
+    context: &Context,
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+) -> Result<Buffer<f32>, OpenclError> {
+    let pixel_count = width
+        .checked_mul(height)
+        .ok_or(OpenclError::IntegerOverflow)?;
+    Ok(unsafe { Buffer::create(context, 0, pixel_count.get(), ptr::null_mut()) }?)
+}
+
+impl OpenclLyapunovRenderer {
+    pub fn new(width: NonZeroUsize, height: NonZeroUsize) -> Result<Self, OpenclError> {
+        let device = default_device()?;
+        let context = Context::from_device(&device)?;
+        let queue = CommandQueue::create_default_with_properties(&context, 0, 0)?;
+        let program = Program::create_and_build_from_source(&context, KERNEL_SOURCE, "")?;
+        let kernel = Kernel::create(&program, "render_lyapunov")?;
+        let buffer = create_output_buffer(&context, width, height)?;
+        Ok(Self {
+            width,
+            height,
+            context,
+            queue,
+            kernel,
+            buffer,
+        })
+    }
+
+    pub fn resize(&mut self, Dimensions { width, height }: Dimensions) -> Result<(), OpenclError> {
+        self.buffer = create_output_buffer(&self.context, width, height)?;
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+}
+
+impl LyapunovRenderer for OpenclLyapunovRenderer {
+    type Error = ClError;
+
+    fn render(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        parameters: &LyapunovParameters,
+        max_iterations: u32,
+    ) -> Result<LyapunovImage, Self::Error> {
+        let pixel_count = self.width.get() * self.height.get();
+        let (sequence, sequence_length) = parameters.sequence.kernel_arguments();
+
+        let kernel_event = unsafe {
+            ExecuteKernel::new(&self.kernel)
+                .set_arg(&(self.width.get() as u64))
+                .set_arg(&(self.height.get() as u64))
+                .set_arg(&center.re)
+                .set_arg(&center.im)
+                .set_arg(&horizontal_radius)
+                .set_arg(&sequence)
+                .set_arg(&sequence_length)
+                .set_arg(&parameters.warmup)
+                .set_arg(&max_iterations)
+                .set_arg(&self.buffer)
+                .set_global_work_size(pixel_count)
+                .enqueue_nd_range(&self.queue)
+        }?;
+
+        let mut buffer = vec![0.0; pixel_count];
+        let read_buffer_event = unsafe {
+            self.queue.enqueue_read_buffer(
+                &self.buffer,
+                CL_NON_BLOCKING,
+                0,
+                &mut buffer,
+                &[kernel_event.get()],
+            )
+        }?;
+        read_buffer_event.wait()?;
+        // Buffer size was already calculated
+        Ok(LyapunovImage::from_buffer(buffer, self.width).unwrap())
+    }
+}
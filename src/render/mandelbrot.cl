@@ -0,0 +1,4 @@
+// The step `iterate` applies unless `Formula::to_opencl` provides another
+double2 next_z(double2 z, double2 c) {
+    return (double2) (z.x * z.x - z.y * z.y + c.x, 2.0 * z.x * z.y + c.y);
+}
@@ -1,9 +1,54 @@
+use std::num::NonZeroUsize;
+
 use num::Complex;
 
 use crate::iteration_image::IterationImage;
 
+use self::{orbit_trap::OrbitTrap, supersampling::Supersampling, transform::ViewTransform};
+
 pub mod cpu;
+pub mod density;
+pub mod lyapunov;
+pub mod newton;
 pub mod opencl;
+pub mod orbit_trap;
+pub mod raymarch;
+pub mod supersampling;
+#[cfg(test)]
+mod tests;
+pub mod transform;
+
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct RenderOptions {
+    pub supersampling: Supersampling,
+    /// Whether to track dz/dc and attach exterior distance estimates to the image.
+    pub distance_estimation: bool,
+    /// Whether to attach normals derived from the potential gradient to the image.
+    pub normals: bool,
+    /// Trap whose closest approach by each orbit is attached to the image.
+    pub orbit_trap: Option<OrbitTrap>,
+    /// Constant c of the Julia set to render instead of the Mandelbrot set, with each point of the
+    /// view as z₀.
+    pub julia: Option<Complex<f64>>,
+    /// Rotation, stretch and skew of the view around its center.
+    pub transform: ViewTransform,
+}
+
+impl RenderOptions {
+    pub fn tracks_derivative(&self) -> bool {
+        self.distance_estimation || self.normals
+    }
+
+    /// These options without distance estimates and normals, which rely on the derivative of
+    /// z² + c and so are not available for user-defined formulas.
+    pub fn without_derivative(&self) -> Self {
+        Self {
+            distance_estimation: false,
+            normals: false,
+            ..*self
+        }
+    }
+}
 
 pub trait Renderer<T> {
     type Error;
@@ -13,5 +58,24 @@ pub trait Renderer<T> {
         center: Complex<T>,
         horizontal_radius: T,
         max_iterations: u32,
+        options: &RenderOptions,
     ) -> Result<IterationImage, Self::Error>;
 }
+
+/// Point of the complex plane at the bottom left corner of `pixel`, identical to
+/// `pixel_to_complex` in `common.cl` without a sample offset.
+pub fn pixel_to_complex(
+    pixel: usize,
+    center: Complex<f64>,
+    horizontal_radius: f64,
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+) -> Complex<f64> {
+    let (width, height) = (width.get(), height.get());
+    let vertical_radius = horizontal_radius * height as f64 / width as f64;
+    let (x, y) = (pixel % width, height - pixel / width - 1);
+    Complex::new(
+        center.re - horizontal_radius + 2.0 * horizontal_radius * x as f64 / width as f64,
+        center.im - vertical_radius + 2.0 * vertical_radius * y as f64 / height as f64,
+    )
+}
@@ -0,0 +1,55 @@
+use std::num::NonZeroUsize;
+
+use num::Complex;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{error::IntegerOverflow, newton_image::NewtonImage, Dimensions};
+
+use super::{super::pixel_to_complex, NewtonRenderer, Polynomial};
+
+pub struct CpuNewtonRenderer {
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+}
+
+impl CpuNewtonRenderer {
+    pub fn new(width: NonZeroUsize, height: NonZeroUsize) -> Result<Self, IntegerOverflow> {
+        width.checked_mul(height).ok_or(IntegerOverflow)?;
+        Ok(Self { width, height })
+    }
+
+    pub fn resize(
+        &mut self,
+        Dimensions { width, height }: Dimensions,
+    ) -> Result<(), IntegerOverflow> {
+        *self = Self::new(width, height)?;
+        Ok(())
+    }
+}
+
+impl NewtonRenderer for CpuNewtonRenderer {
+    type Error = !;
+
+    fn render(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        polynomial: &Polynomial,
+        max_iterations: u32,
+    ) -> Result<NewtonImage, Self::Error> {
+        let buffer = (0..self.width.get() * self.height.get())
+            .into_par_iter()
+            .map(|pixel| {
+                polynomial.newton(
+                    pixel_to_complex(pixel, center, horizontal_radius, self.width, self.height),
+                    max_iterations,
+                )
+            })
+            .collect();
+        // Buffer size was already calculated
+        Ok(
+            NewtonImage::from_buffer(buffer, self.width, polynomial.roots().len(), max_iterations)
+                .unwrap(),
+        )
+    }
+}
@@ -0,0 +1,217 @@
+use std::str::FromStr;
+
+use num::{Complex, Zero};
+
+use crate::{error::PolynomialError, newton_image::NewtonImage};
+
+pub mod cpu;
+pub mod opencl;
+
+/// Squared distance from a root at which Newton's method counts as converged.
+const CONVERGENCE_TOLERANCE: f64 = 1e-12;
+
+/// Iterations used to find the roots of a polynomial given by its coefficients.
+const ROOT_FINDING_ITERATIONS: u32 = 1000;
+
+/// A polynomial together with its roots, which Newton's method is expected to converge to.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Polynomial {
+    /// Coefficients from the constant term up.
+    coefficients: Vec<Complex<f64>>,
+    roots: Vec<Complex<f64>>,
+}
+
+impl Default for Polynomial {
+    /// z³ - 1.
+    fn default() -> Self {
+        Self::from_roots(
+            (0..3)
+                .map(|k| Complex::from_polar(1.0, f64::from(k) * std::f64::consts::TAU / 3.0))
+                .collect(),
+        )
+        .unwrap()
+    }
+}
+
+impl Polynomial {
+    pub fn from_roots(roots: Vec<Complex<f64>>) -> Result<Self, PolynomialError> {
+        if roots.is_empty() {
+            return Err(PolynomialError::Constant);
+        }
+        let mut coefficients = vec![Complex::new(1.0, 0.0)];
+        for &root in &roots {
+            // Multiplies by (z - root)
+            let mut product = vec![Complex::zero(); coefficients.len() + 1];
+            for (power, &coefficient) in coefficients.iter().enumerate() {
+                product[power + 1] += coefficient;
+                product[power] -= coefficient * root;
+            }
+            coefficients = product;
+        }
+        Ok(Self {
+            coefficients,
+            roots,
+        })
+    }
+
+    /// Takes coefficients from the highest power down and finds the roots numerically.
+    pub fn from_coefficients(coefficients: Vec<Complex<f64>>) -> Result<Self, PolynomialError> {
+        let mut coefficients = coefficients
+            .into_iter()
+            .skip_while(Complex::is_zero)
+            .collect::<Vec<_>>();
+        coefficients.reverse();
+        if coefficients.len() < 2 {
+            return Err(PolynomialError::Constant);
+        }
+        let roots = durand_kerner(&coefficients);
+        Ok(Self {
+            coefficients,
+            roots,
+        })
+    }
+
+    /// Coefficients from the constant term up.
+    pub fn coefficients(&self) -> &[Complex<f64>] {
+        &self.coefficients
+    }
+
+    pub fn roots(&self) -> &[Complex<f64>] {
+        &self.roots
+    }
+
+    /// The polynomial and its derivative at `z`, identical to `evaluate` in `newton.cl`.
+    fn evaluate(&self, z: Complex<f64>) -> (Complex<f64>, Complex<f64>) {
+        let mut value = Complex::zero();
+        let mut derivative = Complex::zero();
+        for &coefficient in self.coefficients.iter().rev() {
+            derivative = derivative * z + value;
+            value = value * z + coefficient;
+        }
+        (value, derivative)
+    }
+
+    /// Applies Newton's method to `z`, returning the index of the root it converged to, if any,
+    /// and the iterations it took. Identical to `newton` in `newton.cl`.
+    fn newton(&self, mut z: Complex<f64>, max_iterations: u32) -> RootHit {
+        for iteration in 0..max_iterations {
+            if let Some(root) = self
+                .roots
+                .iter()
+                .position(|&root| (z - root).norm_sqr() < CONVERGENCE_TOLERANCE)
+            {
+                return RootHit {
+                    root: root as u32,
+                    iterations: iteration,
+                };
+            }
+            let (value, derivative) = self.evaluate(z);
+            if derivative.is_zero() {
+                break;
+            }
+            z -= value / derivative;
+        }
+        RootHit {
+            root: RootHit::NO_ROOT,
+            iterations: max_iterations,
+        }
+    }
+}
+
+/// Finds all roots at once with the Durand-Kerner method, taking coefficients from the constant
+/// term up.
+fn durand_kerner(coefficients: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    // Only needs the polynomial to be monic
+    let leading = coefficients[coefficients.len() - 1];
+    let monic = coefficients
+        .iter()
+        .map(|&coefficient| coefficient / leading)
+        .collect::<Vec<_>>();
+    let evaluate = |z: Complex<f64>| {
+        monic
+            .iter()
+            .rev()
+            .fold(Complex::zero(), |value: Complex<f64>, &coefficient| {
+                value * z + coefficient
+            })
+    };
+    let seed = Complex::new(0.4, 0.9);
+    let mut roots = (0..monic.len() - 1)
+        .map(|power| seed.powu(power as u32))
+        .collect::<Vec<_>>();
+    for _ in 0..ROOT_FINDING_ITERATIONS {
+        for index in 0..roots.len() {
+            let root = roots[index];
+            let denominator = roots
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != index)
+                .fold(Complex::new(1.0, 0.0), |product, (_, &other)| {
+                    product * (root - other)
+                });
+            if !denominator.is_zero() {
+                roots[index] = root - evaluate(root) / denominator;
+            }
+        }
+    }
+    roots
+}
+
+impl FromStr for Polynomial {
+    type Err = PolynomialError;
+
+    /// Parses comma-separated coefficients from the highest power down, e.g. `1,0,0,-1` for z³ - 1.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_coefficients(parse_complex_list(s)?)
+    }
+}
+
+/// Parses comma-separated complex numbers such as `1`, `-0.5+0.866i` or `2i`.
+pub fn parse_complex_list(s: &str) -> Result<Vec<Complex<f64>>, PolynomialError> {
+    s.split(',')
+        .map(|number| {
+            let number = number.trim();
+            number
+                .parse()
+                .map_err(|_| PolynomialError::InvalidNumber(number.to_string()))
+        })
+        .collect()
+}
+
+impl std::fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "degree {} with roots ", self.roots.len())?;
+        for (index, root) in self.roots.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:.3}{:+.3}i", root.re, root.im)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which root a sample converged to and how fast, laid out like `root_hit` in `newton.cl`.
+#[repr(C)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct RootHit {
+    pub root: u32,
+    pub iterations: u32,
+}
+
+impl RootHit {
+    /// Marks samples that did not converge within the iteration limit.
+    pub const NO_ROOT: u32 = u32::MAX;
+}
+
+pub trait NewtonRenderer {
+    type Error;
+
+    fn render(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        polynomial: &Polynomial,
+        max_iterations: u32,
+    ) -> Result<NewtonImage, Self::Error>;
+}
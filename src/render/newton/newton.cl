@@ -0,0 +1,44 @@
+// Squared distance from a root at which Newton's method counts as converged, like
+// `CONVERGENCE_TOLERANCE` on the host
+#define CONVERGENCE_TOLERANCE 1e-12
+
+// Laid out like `RootHit` on the host, with `root` set to `UINT_MAX` when nothing converged
+typedef struct {
+    uint root;
+    uint iterations;
+} root_hit;
+
+// Must match `Polynomial::evaluate` on the host, with coefficients from the constant term up
+void evaluate(global const double2* coefficients, uint coefficient_count, double2 z, double2* value, double2* derivative) {
+    *value = (double2) (0.0, 0.0);
+    *derivative = (double2) (0.0, 0.0);
+    for (uint power = coefficient_count; power > 0; power--) {
+        *derivative = complex_multiply(*derivative, z) + *value;
+        *value = complex_multiply(*value, z) + coefficients[power - 1];
+    }
+}
+
+// Must match `Polynomial::newton` on the host
+root_hit newton(double2 z, global const double2* coefficients, uint coefficient_count, global const double2* roots, uint root_count, uint max_iterations) {
+    for (uint iteration = 0; iteration < max_iterations; iteration++) {
+        for (uint root = 0; root < root_count; root++) {
+            const double2 offset = z - roots[root];
+            if (dot(offset, offset) < CONVERGENCE_TOLERANCE) {
+                return (root_hit) { root, iteration };
+            }
+        }
+        double2 value, derivative;
+        evaluate(coefficients, coefficient_count, z, &value, &derivative);
+        if (derivative.x == 0.0 && derivative.y == 0.0) {
+            break;
+        }
+        z -= complex_divide(value, derivative);
+    }
+    return (root_hit) { UINT_MAX, max_iterations };
+}
+
+kernel void render_newton(ulong width, ulong height, double center_re, double center_im, double radius, global const double2* coefficients, uint coefficient_count, global const double2* roots, uint root_count, uint max_iterations, global root_hit* output) {
+    const size_t pixel = get_global_id(0);
+    const double2 z = pixel_to_complex(width, height, center_re, center_im, radius, pixel, (double2) (0.0, 0.0));
+    output[pixel] = newton(z, coefficients, coefficient_count, roots, root_count, max_iterations);
+}
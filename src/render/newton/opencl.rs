@@ -0,0 +1,131 @@
+use std::{ffi::c_void, num::NonZeroUsize, ptr};
+
+use num::Complex;
+use opencl3::{
+    command_queue::CommandQueue,
+    context::Context,
+    error_codes::ClError,
+    kernel::{ExecuteKernel, Kernel},
+    memory::{Buffer, CL_MEM_COPY_HOST_PTR, CL_MEM_READ_ONLY},
+    program::Program,
+    types::CL_NON_BLOCKING,
+};
+
+use crate::{error::OpenclError, newton_image::NewtonImage, Dimensions};
+
+use super::{super::opencl::default_device, NewtonRenderer, Polynomial, RootHit};
+
+static KERNEL_SOURCE: &str = concat!(
+    include_str!("../common.cl"),
+    "\n",
+    include_str!("../complex.cl"),
+    "\n",
+    include_str!("newton.cl")
+);
+
+pub struct OpenclNewtonRenderer {
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+    context: Context,
+    queue: CommandQueue,
+    kernel: Kernel,
+    buffer: Buffer<RootHit>,
+}
+
+fn create_output_buffer(
+    context: &Context,
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+) -> Result<Buffer<RootHit>, OpenclError> {
+    let pixel_count = width
+        .checked_mul(height)
+        .ok_or(OpenclError::IntegerOverflow)?;
+    Ok(unsafe { Buffer::create(context, 0, pixel_count.get(), ptr::null_mut()) }?)
+}
+
+impl OpenclNewtonRenderer {
+    pub fn new(width: NonZeroUsize, height: NonZeroUsize) -> Result<Self, OpenclError> {
+        let device = default_device()?;
+        let context = Context::from_device(&device)?;
+        let queue = CommandQueue::create_default_with_properties(&context, 0, 0)?;
+        let program = Program::create_and_build_from_source(&context, KERNEL_SOURCE, "")?;
+        let kernel = Kernel::create(&program, "render_newton")?;
+        let buffer = create_output_buffer(&context, width, height)?;
+        Ok(Self {
+            width,
+            height,
+            context,
+            queue,
+            kernel,
+            buffer,
+        })
+    }
+
+    pub fn resize(&mut self, Dimensions { width, height }: Dimensions) -> Result<(), OpenclError> {
+        self.buffer = create_output_buffer(&self.context, width, height)?;
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    fn upload(&self, data: &[Complex<f64>]) -> Result<Buffer<Complex<f64>>, ClError> {
+        unsafe {
+            Buffer::create(
+                &self.context,
+                CL_MEM_READ_ONLY | CL_MEM_COPY_HOST_PTR,
+                data.len(),
+                data.as_ptr() as *mut c_void,
+            )
+        }
+    }
+}
+
+impl NewtonRenderer for OpenclNewtonRenderer {
+    type Error = ClError;
+
+    fn render(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        polynomial: &Polynomial,
+        max_iterations: u32,
+    ) -> Result<NewtonImage, Self::Error> {
+        let pixel_count = self.width.get() * self.height.get();
+        let coefficients = self.upload(polynomial.coefficients())?;
+        let roots = self.upload(polynomial.roots())?;
+
+        let kernel_event = unsafe {
+            ExecuteKernel::new(&self.kernel)
+                .set_arg(&(self.width.get() as u64))
+                .set_arg(&(self.height.get() as u64))
+                .set_arg(&center.re)
+                .set_arg(&center.im)
+                .set_arg(&horizontal_radius)
+                .set_arg(&coefficients)
+                .set_arg(&(polynomial.coefficients().len() as u32))
+                .set_arg(&roots)
+                .set_arg(&(polynomial.roots().len() as u32))
+                .set_arg(&max_iterations)
+                .set_arg(&self.buffer)
+                .set_global_work_size(pixel_count)
+                .enqueue_nd_range(&self.queue)
+        }?;
+
+        let mut buffer = vec![RootHit::default(); pixel_count];
+        let read_buffer_event = unsafe {
+            self.queue.enqueue_read_buffer(
+                &self.buffer,
+                CL_NON_BLOCKING,
+                0,
+                &mut buffer,
+                &[kernel_event.get()],
+            )
+        }?;
+        read_buffer_event.wait()?;
+        // Buffer size was already calculated
+        Ok(
+            NewtonImage::from_buffer(buffer, self.width, polynomial.roots().len(), max_iterations)
+                .unwrap(),
+        )
+    }
+}
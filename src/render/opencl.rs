@@ -1,22 +1,60 @@
-use std::{num::NonZeroUsize, ptr};
+use std::{ffi::c_void, num::NonZeroUsize, ptr};
 
 use num::Complex;
 use opencl3::{
     command_queue::CommandQueue,
     context::Context,
-    device::{self, Device, CL_DEVICE_TYPE_GPU},
+    device::{self, Device, CL_DEVICE_TYPE_ALL, CL_DEVICE_TYPE_GPU},
     error_codes::ClError,
+    event::Event,
     kernel::{ExecuteKernel, Kernel},
-    memory::Buffer,
+    memory::{Buffer, CL_MEM_COPY_HOST_PTR, CL_MEM_READ_ONLY},
     program::Program,
-    types::CL_NON_BLOCKING,
+    types::{CL_BLOCKING, CL_NON_BLOCKING},
 };
 
-use crate::{error::OpenclError, iteration_image::IterationImage, Dimensions};
+use crate::{
+    colouring::{Colouring, Shading, DISTANCE_FALLOFF, TRAP_FALLOFF, TRAP_ITERATION_STEP},
+    error::{InvalidBufferSize, OpenclError},
+    formula::Formula,
+    iteration_image::IterationImage,
+    palette::Palette,
+    rgb_image::RgbImage,
+    Dimensions,
+};
+
+use super::{
+    orbit_trap::TrapHit,
+    supersampling::{edge_pixels, Supersampling, MAX_SAMPLES_PER_PIXEL},
+    RenderOptions, Renderer,
+};
+
+/// Where the iteration step goes in the program built from `program_source`.
+static KERNEL_SOURCE_PARTS: [&str; 3] = [
+    concat!(include_str!("common.cl"), "\n", include_str!("complex.cl")),
+    include_str!("mandelbrot.cl"),
+    include_str!("kernel.cl"),
+];
 
-use super::Renderer;
+/// The rendering program with `formula`'s iteration step, or z² + c if there is none.
+fn program_source(formula: Option<&Formula>) -> String {
+    let [before, mandelbrot, after] = KERNEL_SOURCE_PARTS;
+    let step = formula.map_or_else(|| mandelbrot.to_string(), Formula::to_opencl);
+    [before, &step, after].join("\n")
+}
 
-static KERNEL_SOURCE: &str = include_str!("kernel.cl");
+/// The rendering, adaptive supersampling and colouring kernels.
+fn build_kernels(
+    context: &Context,
+    formula: Option<&Formula>,
+) -> Result<(Kernel, Kernel, Kernel), OpenclError> {
+    let program = Program::create_and_build_from_source(context, &program_source(formula), "")?;
+    Ok((
+        Kernel::create(&program, "render")?,
+        Kernel::create(&program, "render_pixels")?,
+        Kernel::create(&program, "colour")?,
+    ))
+}
 
 pub struct OpenclRenderer {
     width: NonZeroUsize,
@@ -24,38 +62,214 @@ pub struct OpenclRenderer {
     context: Context,
     queue: CommandQueue,
     kernel: Kernel,
+    pixels_kernel: Kernel,
     buffer: Buffer<u32>,
+    distance_buffer: Buffer<f32>,
+    normal_buffer: Buffer<[f32; 2]>,
+    trap_buffer: Buffer<TrapHit>,
+    colour_kernel: Kernel,
+    rgb_buffer: Buffer<u8>,
+    /// The palette last uploaded for `colour_kernel`, to only upload it again once it changes.
+    palette: Option<(Palette, Buffer<u8>)>,
+    /// Whether the kernels iterate a user-defined formula, which has no derivative to track.
+    has_formula: bool,
+    /// Counts the times the output buffers were written or replaced, to tell which
+    /// `DeviceImage` they hold.
+    generation: u64,
+}
+
+/// Samples left on the device by `OpenclRenderer::render_on_device`, waiting to be coloured. They
+/// stay in the renderer's output buffers, so any later render replaces them.
+pub struct DeviceImage {
+    /// The renderer's generation once the samples were written.
+    generation: u64,
+    samples_per_pixel: NonZeroUsize,
+    max_iterations: u32,
+    options: RenderOptions,
+}
+
+/// Per-sample results read back from the device.
+struct Samples {
+    iterations: Vec<u32>,
+    distances: Option<Vec<f32>>,
+    normals: Option<Vec<[f32; 2]>>,
+    trap_hits: Option<Vec<TrapHit>>,
+}
+
+/// Repeats every single-sample value `samples_per_pixel` times.
+fn expand<T: Copy>(single_sample_pass: &[T], samples_per_pixel: usize) -> Vec<T> {
+    single_sample_pass
+        .iter()
+        .flat_map(|&value| std::iter::repeat_n(value, samples_per_pixel))
+        .collect()
+}
+
+/// Writes the samples of the listed pixels over their slots in `buffer`.
+fn scatter<T: Copy>(buffer: &mut [T], pixels: &[u64], samples: &[T], samples_per_pixel: usize) {
+    for (&pixel, samples) in pixels.iter().zip(samples.chunks_exact(samples_per_pixel)) {
+        let start = pixel as usize * samples_per_pixel;
+        buffer[start..start + samples_per_pixel].copy_from_slice(samples);
+    }
+}
+
+fn scatter_channel<T: Copy>(
+    buffer: &mut Option<Vec<T>>,
+    pixels: &[u64],
+    samples: &Option<Vec<T>>,
+    samples_per_pixel: usize,
+) {
+    if let (Some(buffer), Some(samples)) = (buffer, samples) {
+        scatter(buffer, pixels, samples, samples_per_pixel);
+    }
+}
+
+impl Samples {
+    /// Turns a single-sample pass into a full buffer with every sample equal to its pixel's.
+    fn expand(self, samples_per_pixel: usize) -> Self {
+        Self {
+            iterations: expand(&self.iterations, samples_per_pixel),
+            distances: self
+                .distances
+                .map(|distances| expand(&distances, samples_per_pixel)),
+            normals: self
+                .normals
+                .map(|normals| expand(&normals, samples_per_pixel)),
+            trap_hits: self
+                .trap_hits
+                .map(|trap_hits| expand(&trap_hits, samples_per_pixel)),
+        }
+    }
+
+    /// Overwrites the listed pixels with the samples rendered for them alone.
+    fn scatter(&mut self, pixels: &[u64], samples: &Self, samples_per_pixel: usize) {
+        scatter(
+            &mut self.iterations,
+            pixels,
+            &samples.iterations,
+            samples_per_pixel,
+        );
+        scatter_channel(
+            &mut self.distances,
+            pixels,
+            &samples.distances,
+            samples_per_pixel,
+        );
+        scatter_channel(
+            &mut self.normals,
+            pixels,
+            &samples.normals,
+            samples_per_pixel,
+        );
+        scatter_channel(
+            &mut self.trap_hits,
+            pixels,
+            &samples.trap_hits,
+            samples_per_pixel,
+        );
+    }
+
+    fn into_image(
+        self,
+        width: NonZeroUsize,
+        samples_per_pixel: NonZeroUsize,
+        max_iterations: u32,
+    ) -> Result<IterationImage, InvalidBufferSize> {
+        let mut image =
+            IterationImage::from_buffer(self.iterations, width, samples_per_pixel, max_iterations)?;
+        if let Some(distances) = self.distances {
+            image = image.with_distances(distances)?;
+        }
+        if let Some(normals) = self.normals {
+            image = image.with_normals(normals)?;
+        }
+        if let Some(trap_hits) = self.trap_hits {
+            image = image.with_trap_hits(trap_hits)?;
+        }
+        Ok(image)
+    }
+}
+
+fn create_rgb_buffer(
+    context: &Context,
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+) -> Result<Buffer<u8>, OpenclError> {
+    let byte_count = width
+        .checked_mul(height)
+        .and_then(|pixel_count| pixel_count.checked_mul(NonZeroUsize::new(3).unwrap()))
+        .ok_or(OpenclError::IntegerOverflow)?;
+    Ok(unsafe { Buffer::create(context, 0, byte_count.get(), ptr::null_mut()) }?)
+}
+
+fn create_output_buffer<T>(
+    context: &Context,
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+) -> Result<Buffer<T>, OpenclError> {
+    let sample_count = width
+        .checked_mul(height)
+        .and_then(|pixel_count| {
+            pixel_count.checked_mul(NonZeroUsize::new(MAX_SAMPLES_PER_PIXEL).unwrap())
+        })
+        .ok_or(OpenclError::IntegerOverflow)?;
+    Ok(unsafe { Buffer::create(context, 0, sample_count.get(), ptr::null_mut()) }?)
+}
+
+/// The first GPU, or the first device of any kind if there is none, which every OpenCL renderer
+/// runs on.
+pub fn default_device() -> Result<Device, OpenclError> {
+    let gpus = device::get_all_devices(CL_DEVICE_TYPE_GPU)?;
+    let devices = if gpus.is_empty() {
+        device::get_all_devices(CL_DEVICE_TYPE_ALL)?
+    } else {
+        gpus
+    };
+    Ok(Device::new(*devices.first().ok_or(OpenclError::NoDevices)?))
+}
+
+/// Every device of every platform.
+pub fn all_devices() -> Result<Vec<Device>, OpenclError> {
+    Ok(device::get_all_devices(CL_DEVICE_TYPE_ALL)?
+        .into_iter()
+        .map(Device::new)
+        .collect())
 }
 
 impl OpenclRenderer {
     pub fn new(width: NonZeroUsize, height: NonZeroUsize) -> Result<Self, OpenclError> {
-        let device = Device::new(
-            *device::get_all_devices(CL_DEVICE_TYPE_GPU)?
-                .first()
-                .ok_or(OpenclError::NoDevices)?,
-        );
-        let context = Context::from_device(&device)?;
+        Self::with_device(&default_device()?, width, height)
+    }
+
+    /// Renderer running on `device` instead of the default one.
+    pub fn with_device(
+        device: &Device,
+        width: NonZeroUsize,
+        height: NonZeroUsize,
+    ) -> Result<Self, OpenclError> {
+        let context = Context::from_device(device)?;
         let queue = CommandQueue::create_default_with_properties(&context, 0, 0)?;
-        let program = Program::create_and_build_from_source(&context, KERNEL_SOURCE, "")?;
-        let kernel = Kernel::create(&program, "render")?;
-        let buffer = unsafe {
-            Buffer::create(
-                &context,
-                0,
-                width
-                    .checked_mul(height)
-                    .ok_or(OpenclError::IntegerOverflow)?
-                    .get(),
-                ptr::null_mut(),
-            )
-        }?;
+        let (kernel, pixels_kernel, colour_kernel) = build_kernels(&context, None)?;
+        let buffer = create_output_buffer(&context, width, height)?;
+        let distance_buffer = create_output_buffer(&context, width, height)?;
+        let normal_buffer = create_output_buffer(&context, width, height)?;
+        let trap_buffer = create_output_buffer(&context, width, height)?;
+        let rgb_buffer = create_rgb_buffer(&context, width, height)?;
         Ok(Self {
             width,
             height,
             context,
             queue,
             kernel,
+            pixels_kernel,
             buffer,
+            distance_buffer,
+            normal_buffer,
+            trap_buffer,
+            colour_kernel,
+            rgb_buffer,
+            palette: None,
+            has_formula: false,
+            generation: 0,
         })
     }
 
@@ -64,63 +278,314 @@ impl OpenclRenderer {
     }
 
     pub fn resize(&mut self, Dimensions { width, height }: Dimensions) -> Result<(), OpenclError> {
-        self.buffer = unsafe {
+        self.buffer = create_output_buffer(&self.context, width, height)?;
+        self.distance_buffer = create_output_buffer(&self.context, width, height)?;
+        self.normal_buffer = create_output_buffer(&self.context, width, height)?;
+        self.trap_buffer = create_output_buffer(&self.context, width, height)?;
+        self.rgb_buffer = create_rgb_buffer(&self.context, width, height)?;
+        self.generation += 1;
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    /// Rebuilds the kernels to iterate `formula` instead of z² + c, or z² + c again for `None`.
+    pub fn set_formula(&mut self, formula: Option<&Formula>) -> Result<(), OpenclError> {
+        (self.kernel, self.pixels_kernel, self.colour_kernel) =
+            build_kernels(&self.context, formula)?;
+        self.has_formula = formula.is_some();
+        Ok(())
+    }
+
+    fn upload<T>(&self, data: &[T]) -> Result<Buffer<T>, ClError> {
+        unsafe {
             Buffer::create(
                 &self.context,
-                0,
-                width
-                    .checked_mul(height)
-                    .ok_or(OpenclError::IntegerOverflow)?
-                    .get(),
-                ptr::null_mut(),
+                CL_MEM_READ_ONLY | CL_MEM_COPY_HOST_PTR,
+                data.len(),
+                data.as_ptr() as *mut c_void,
             )
+        }
+    }
+
+    fn read_output<T: Default + Clone>(
+        &self,
+        buffer: &Buffer<T>,
+        event: &Event,
+        length: usize,
+    ) -> Result<Vec<T>, ClError> {
+        let mut output = vec![T::default(); length];
+        let read_buffer_event = unsafe {
+            self.queue
+                .enqueue_read_buffer(buffer, CL_NON_BLOCKING, 0, &mut output, &[event.get()])
         }?;
-        self.width = width;
-        self.height = height;
-        Ok(())
+        read_buffer_event.wait()?;
+        Ok(output)
     }
-}
 
-impl Renderer<f64> for OpenclRenderer {
-    type Error = ClError;
+    /// Renders every sample of every pixel, or only those of `pixels` when given.
+    fn render_samples(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        max_iterations: u32,
+        options: &RenderOptions,
+        pixels: Option<&[u64]>,
+    ) -> Result<Samples, ClError> {
+        let (kernel_event, work_size) =
+            self.enqueue_samples(center, horizontal_radius, max_iterations, options, pixels)?;
+        Ok(Samples {
+            iterations: self.read_output(&self.buffer, &kernel_event, work_size)?,
+            distances: options
+                .distance_estimation
+                .then(|| self.read_output(&self.distance_buffer, &kernel_event, work_size))
+                .transpose()?,
+            normals: options
+                .normals
+                .then(|| self.read_output(&self.normal_buffer, &kernel_event, work_size))
+                .transpose()?,
+            trap_hits: options
+                .orbit_trap
+                .map(|_| self.read_output(&self.trap_buffer, &kernel_event, work_size))
+                .transpose()?,
+        })
+    }
 
-    fn render(
+    /// Enqueues the kernel filling the output buffers, returning its event and the sample count.
+    fn enqueue_samples(
         &mut self,
         center: Complex<f64>,
         horizontal_radius: f64,
         max_iterations: u32,
-    ) -> Result<IterationImage, Self::Error> {
-        // Shouldn't overflow because the check was done during object construction
-        let work_size = self.width.get() * self.height.get();
+        options: &RenderOptions,
+        pixels: Option<&[u64]>,
+    ) -> Result<(Event, usize), ClError> {
+        let supersampling = options.supersampling;
+        let samples_per_pixel = supersampling.samples_per_pixel().get();
+        let pixel_count = pixels.map_or(self.width.get() * self.height.get(), <[u64]>::len);
+        let work_size = pixel_count * samples_per_pixel;
+        let offsets = self.upload(supersampling.offsets())?;
+        let pixels = pixels.map(|pixels| self.upload(pixels)).transpose()?;
+        let (trap_shape, trap_parameters) = options
+            .orbit_trap
+            .map_or((0, [0.0; 4]), |orbit_trap| orbit_trap.kernel_arguments());
+        let julia_c = options.julia.map_or([0.0; 2], |julia| [julia.re, julia.im]);
 
         let kernel_event = unsafe {
-            ExecuteKernel::new(&self.kernel)
+            let mut execute_kernel = ExecuteKernel::new(if pixels.is_some() {
+                &self.pixels_kernel
+            } else {
+                &self.kernel
+            });
+            execute_kernel
                 .set_arg(&(self.width.get() as u64))
+                .set_arg(&(self.height.get() as u64))
                 .set_arg(&center.re)
                 .set_arg(&center.im)
                 .set_arg(&horizontal_radius)
                 .set_arg(&max_iterations)
+                .set_arg(&offsets)
+                .set_arg(&(samples_per_pixel as u32))
+                .set_arg(&supersampling.jitter())
+                .set_arg(&u32::from(options.distance_estimation))
+                .set_arg(&u32::from(options.normals))
+                .set_arg(&trap_shape)
+                .set_arg(&trap_parameters)
+                .set_arg(&u32::from(options.julia.is_some()))
+                .set_arg(&julia_c)
+                .set_arg(&options.transform.kernel_arguments());
+            if let Some(pixels) = &pixels {
+                execute_kernel.set_arg(pixels);
+            }
+            execute_kernel
                 .set_arg(&self.buffer)
+                .set_arg(&self.distance_buffer)
+                .set_arg(&self.normal_buffer)
+                .set_arg(&self.trap_buffer)
                 .set_global_work_size(work_size)
                 .enqueue_nd_range(&self.queue)
         }?;
+        self.generation += 1;
+        Ok((kernel_event, work_size))
+    }
 
-        let mut iteration_image_buffer = vec![0; work_size];
-        let read_buffer_event = unsafe {
-            self.queue.enqueue_read_buffer(
-                &self.buffer,
-                CL_NON_BLOCKING,
-                0,
-                &mut iteration_image_buffer,
-                &[kernel_event.get()],
-            )
-        }?;
-        read_buffer_event.wait()?;
+    /// Whether `colour` can reproduce what `colouring` does on the host for images rendered with
+    /// `options`. Adaptive supersampling assembles its samples on the host and histogram
+    /// equalisation needs the whole image there.
+    pub fn can_colour(options: &RenderOptions, colouring: &Colouring) -> bool {
+        !options.supersampling.is_adaptive() && colouring.shading != Shading::HistogramEqualised
+    }
+
+    /// Renders like `Renderer::render`, but leaves the samples on the device for `colour`. Only
+    /// valid for options and colourings `can_colour` accepts.
+    pub fn render_on_device(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        max_iterations: u32,
+        options: &RenderOptions,
+    ) -> Result<DeviceImage, ClError> {
+        let options = &self.supported_options(options);
+        let (kernel_event, _) =
+            self.enqueue_samples(center, horizontal_radius, max_iterations, options, None)?;
+        kernel_event.wait()?;
+        Ok(DeviceImage {
+            generation: self.generation,
+            samples_per_pixel: options.supersampling.samples_per_pixel(),
+            max_iterations,
+            options: *options,
+        })
+    }
+
+    /// `options` without what the current kernels cannot render, like `ScalarCpuRenderer` does.
+    fn supported_options(&self, options: &RenderOptions) -> RenderOptions {
+        if self.has_formula {
+            options.without_derivative()
+        } else {
+            *options
+        }
+    }
 
+    /// Whether the output buffers still hold the samples of `image`.
+    pub fn holds(&self, image: &DeviceImage) -> bool {
+        image.generation == self.generation
+    }
+
+    /// Reads back only the iteration counts of `image`, without the distances, normals or trap
+    /// hits colouring them needs. Fails if the renderer no longer `holds` it.
+    pub fn read_iterations(&self, image: &DeviceImage) -> Result<IterationImage, OpenclError> {
+        if !self.holds(image) {
+            return Err(OpenclError::StaleDeviceImage);
+        }
+        let sample_count = self.width.get() * self.height.get() * image.samples_per_pixel.get();
+        let mut iterations = vec![0; sample_count];
+        unsafe {
+            self.queue
+                .enqueue_read_buffer(&self.buffer, CL_BLOCKING, 0, &mut iterations, &[])
+        }?;
         // Buffer size was already calculated
-        Ok(
-            IterationImage::from_buffer(iteration_image_buffer, self.width, max_iterations)
-                .unwrap(),
+        Ok(IterationImage::from_buffer(
+            iterations,
+            self.width,
+            image.samples_per_pixel,
+            image.max_iterations,
         )
+        .unwrap())
+    }
+
+    /// Colours the samples of `image` and reads back only the result. Fails if the renderer no
+    /// longer `holds` it.
+    pub fn colour(
+        &mut self,
+        image: &DeviceImage,
+        colouring: &Colouring,
+    ) -> Result<RgbImage, OpenclError> {
+        if !self.holds(image) {
+            return Err(OpenclError::StaleDeviceImage);
+        }
+        if self
+            .palette
+            .as_ref()
+            .is_none_or(|(palette, _)| *palette != colouring.palette)
+        {
+            let buffer = self.upload(colouring.palette.as_bytes())?;
+            self.palette = Some((colouring.palette.clone(), buffer));
+        }
+        // Uploaded above
+        let (palette, palette_buffer) = self.palette.as_ref().unwrap();
+        let shading: u32 = match colouring.shading {
+            Shading::EscapeTime | Shading::HistogramEqualised => 0,
+            Shading::DistanceEstimate => 1,
+            Shading::Lighting => 2,
+            Shading::OrbitTrap => 3,
+        };
+        let pixel_count = self.width.get() * self.height.get();
+
+        let kernel_event = unsafe {
+            ExecuteKernel::new(&self.colour_kernel)
+                .set_arg(&self.buffer)
+                .set_arg(&self.distance_buffer)
+                .set_arg(&self.normal_buffer)
+                .set_arg(&self.trap_buffer)
+                .set_arg(&u32::from(image.options.distance_estimation))
+                .set_arg(&u32::from(image.options.normals))
+                .set_arg(&u32::from(image.options.orbit_trap.is_some()))
+                .set_arg(&(image.samples_per_pixel.get() as u32))
+                .set_arg(&image.max_iterations)
+                .set_arg(&shading)
+                .set_arg(&colouring.light.kernel_argument())
+                .set_arg(&DISTANCE_FALLOFF)
+                .set_arg(&TRAP_FALLOFF)
+                .set_arg(&TRAP_ITERATION_STEP)
+                .set_arg(palette_buffer)
+                .set_arg(&((palette.as_bytes().len() / 3) as u32))
+                .set_arg(&colouring.cycle_offset.rem_euclid(1.0))
+                .set_arg(&self.rgb_buffer)
+                .set_global_work_size(pixel_count)
+                .enqueue_nd_range(&self.queue)
+        }?;
+
+        let rgb = self.read_output(&self.rgb_buffer, &kernel_event, pixel_count * 3)?;
+        // Buffer size was already calculated
+        Ok(RgbImage::from_buffer(rgb, self.width).unwrap())
+    }
+}
+
+impl Renderer<f64> for OpenclRenderer {
+    type Error = ClError;
+
+    fn render(
+        &mut self,
+        center: Complex<f64>,
+        horizontal_radius: f64,
+        max_iterations: u32,
+        options: &RenderOptions,
+    ) -> Result<IterationImage, Self::Error> {
+        let options = &self.supported_options(options);
+        let supersampling = options.supersampling;
+        let samples_per_pixel = supersampling.samples_per_pixel();
+
+        let samples = if supersampling.is_adaptive() {
+            // A single sample per pixel decides where supersampling is worth it
+            let single_sample_pass = self.render_samples(
+                center,
+                horizontal_radius,
+                max_iterations,
+                &RenderOptions {
+                    supersampling: Supersampling::default(),
+                    ..*options
+                },
+                None,
+            )?;
+            let edges = edge_pixels(
+                &single_sample_pass.iterations,
+                single_sample_pass.distances.as_deref(),
+                self.width,
+                max_iterations,
+            );
+            let pixels = (0..edges.len() as u64)
+                .filter(|&pixel| edges[pixel as usize])
+                .collect::<Vec<_>>();
+
+            let mut samples = single_sample_pass.expand(samples_per_pixel.get());
+            if !pixels.is_empty() {
+                let supersampled = self.render_samples(
+                    center,
+                    horizontal_radius,
+                    max_iterations,
+                    options,
+                    Some(&pixels),
+                )?;
+                samples.scatter(&pixels, &supersampled, samples_per_pixel.get());
+            }
+            samples
+        } else {
+            self.render_samples(center, horizontal_radius, max_iterations, options, None)?
+        };
+
+        // Buffer size was already calculated
+        Ok(samples
+            .into_image(self.width, samples_per_pixel, max_iterations)
+            .unwrap())
     }
 }
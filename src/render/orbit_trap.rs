@@ -0,0 +1,116 @@
+use std::f64::consts::FRAC_PI_4;
+
+use num::Complex;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum OrbitTrap {
+    Point {
+        center: Complex<f64>,
+    },
+    Cross {
+        center: Complex<f64>,
+    },
+    Circle {
+        center: Complex<f64>,
+        radius: f64,
+    },
+    Line {
+        point: Complex<f64>,
+        angle: f64,
+    },
+    /// Pickover's stalks: the axes, but only points within `width` of them are trapped.
+    PickoverStalks {
+        width: f64,
+    },
+}
+
+impl Default for OrbitTrap {
+    fn default() -> Self {
+        Self::Point {
+            center: Complex::new(0.0, 0.0),
+        }
+    }
+}
+
+impl OrbitTrap {
+    /// Cycles through every trap shape with its default parameters.
+    pub fn next(self) -> Self {
+        let origin = Complex::new(0.0, 0.0);
+        match self {
+            Self::Point { .. } => Self::Cross { center: origin },
+            Self::Cross { .. } => Self::Circle {
+                center: origin,
+                radius: 1.0,
+            },
+            Self::Circle { .. } => Self::Line {
+                point: origin,
+                angle: FRAC_PI_4,
+            },
+            Self::Line { .. } => Self::PickoverStalks { width: 0.05 },
+            Self::PickoverStalks { .. } => Self::default(),
+        }
+    }
+
+    /// Shape identifier and parameters in the layout `orbit_trap_distance` in `kernel.cl` expects,
+    /// padded to a `double4`.
+    pub fn kernel_arguments(&self) -> (u32, [f64; 4]) {
+        match *self {
+            Self::Point { center } => (1, [center.re, center.im, 0.0, 0.0]),
+            Self::Cross { center } => (2, [center.re, center.im, 0.0, 0.0]),
+            Self::Circle { center, radius } => (3, [center.re, center.im, radius, 0.0]),
+            Self::Line { point, angle } => (4, [point.re, point.im, angle, 0.0]),
+            Self::PickoverStalks { width } => (5, [0.0, 0.0, width, 0.0]),
+        }
+    }
+
+    /// Distance from `z` to the trap, infinite if the trap does not catch it at all.
+    pub fn distance(&self, z: Complex<f64>) -> f64 {
+        match *self {
+            Self::Point { center } => (z - center).norm(),
+            Self::Cross { center } => (z.re - center.re).abs().min((z.im - center.im).abs()),
+            Self::Circle { center, radius } => ((z - center).norm() - radius).abs(),
+            Self::Line { point, angle } => {
+                let offset = z - point;
+                (offset.im * angle.cos() - offset.re * angle.sin()).abs()
+            }
+            Self::PickoverStalks { width } => {
+                let distance = z.re.abs().min(z.im.abs());
+                if distance < width {
+                    distance / width
+                } else {
+                    f64::INFINITY
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for OrbitTrap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Point { .. } => write!(f, "Point"),
+            Self::Cross { .. } => write!(f, "Cross"),
+            Self::Circle { .. } => write!(f, "Circle"),
+            Self::Line { .. } => write!(f, "Line"),
+            Self::PickoverStalks { .. } => write!(f, "Pickover stalks"),
+        }
+    }
+}
+
+/// Closest approach of an orbit to the trap. Laid out like `trap_hit` in `kernel.cl`.
+#[repr(C)]
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct TrapHit {
+    pub distance: f32,
+    /// Iteration the closest approach happened at, which orbit trap shading picks the colour by.
+    pub iteration: u32,
+}
+
+impl Default for TrapHit {
+    fn default() -> Self {
+        Self {
+            distance: f32::INFINITY,
+            iteration: 0,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::num::NonZeroUsize;
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{error::IntegerOverflow, rgb_image::RgbImage, Dimensions};
+
+use super::{RayMarchParameters, RayMarchRenderer};
+
+pub struct CpuRayMarchRenderer {
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+}
+
+impl CpuRayMarchRenderer {
+    pub fn new(width: NonZeroUsize, height: NonZeroUsize) -> Result<Self, IntegerOverflow> {
+        width
+            .checked_mul(height)
+            .and_then(|pixel_count| pixel_count.checked_mul(NonZeroUsize::new(3).unwrap()))
+            .ok_or(IntegerOverflow)?;
+        Ok(Self { width, height })
+    }
+
+    pub fn resize(
+        &mut self,
+        Dimensions { width, height }: Dimensions,
+    ) -> Result<(), IntegerOverflow> {
+        *self = Self::new(width, height)?;
+        Ok(())
+    }
+}
+
+impl RayMarchRenderer for CpuRayMarchRenderer {
+    type Error = !;
+
+    fn render(&mut self, parameters: &RayMarchParameters) -> Result<RgbImage, Self::Error> {
+        let (width, height) = (self.width.get(), self.height.get());
+        let buffer = (0..width * height)
+            .into_par_iter()
+            .flat_map_iter(|pixel| parameters.shade(pixel, width, height))
+            .collect();
+        // Buffer size was already calculated
+        Ok(RgbImage::from_buffer(buffer, self.width).unwrap())
+    }
+}
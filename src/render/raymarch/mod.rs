@@ -0,0 +1,353 @@
+use std::f64::consts::FRAC_PI_2;
+
+use crate::rgb_image::RgbImage;
+
+pub use self::{quaternion::Quaternion, vector::Vector};
+
+pub mod cpu;
+pub mod opencl;
+mod quaternion;
+mod vector;
+
+// Every constant below is repeated in `raymarch.cl`
+
+const MANDELBULB_ITERATIONS: u32 = 12;
+const MANDELBULB_BAILOUT: f64 = 2.0;
+const MANDELBOX_ITERATIONS: u32 = 16;
+const MANDELBOX_MIN_RADIUS_SQUARED: f64 = 0.25;
+const MANDELBOX_FIXED_RADIUS_SQUARED: f64 = 1.0;
+const QUATERNION_JULIA_ITERATIONS: u32 = 16;
+const QUATERNION_JULIA_BAILOUT: f64 = 4.0;
+
+const MAX_STEPS: u32 = 256;
+const MAX_DISTANCE: f64 = 50.0;
+/// Fraction of a pixel's footprint at which a ray counts as hitting the surface.
+const HIT_PRECISION: f64 = 0.5;
+/// Offset used to take the gradient of the distance estimate for surface normals.
+const NORMAL_OFFSET: f64 = 1e-5;
+/// How sharp soft shadows are; higher values give harder edges.
+const SHADOW_SHARPNESS: f64 = 8.0;
+const SHADOW_STEPS: u32 = 64;
+const AMBIENT_OCCLUSION_SAMPLES: u32 = 5;
+const AMBIENT_OCCLUSION_SPACING: f64 = 0.02;
+const AMBIENT_LIGHT: f64 = 0.2;
+
+const LIGHT_DIRECTION: Vector = Vector::new(0.577, 0.577, -0.577);
+const SURFACE_COLOUR: Vector = Vector::new(0.9, 0.75, 0.55);
+const SKY_COLOUR: Vector = Vector::new(0.35, 0.5, 0.75);
+const HORIZON_COLOUR: Vector = Vector::new(0.05, 0.05, 0.1);
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Shape {
+    Mandelbulb {
+        power: f64,
+    },
+    Mandelbox {
+        scale: f64,
+    },
+    /// Julia set of q² + c in four dimensions, cut at `slice` along the fourth.
+    QuaternionJulia {
+        constant: Quaternion,
+        slice: f64,
+    },
+}
+
+impl Shape {
+    pub const MANDELBULB: Self = Self::Mandelbulb { power: 8.0 };
+    pub const MANDELBOX: Self = Self::Mandelbox { scale: 2.0 };
+    pub const QUATERNION_JULIA: Self = Self::QuaternionJulia {
+        constant: Quaternion::new(-0.2, 0.8, 0.0, 0.0),
+        slice: 0.0,
+    };
+
+    /// Raises or lowers the bulb's power by `steps`, the box's scale by a quarter per step or the
+    /// Julia set's slice by a twentieth per step. Box scales between -1 and 1 are skipped since
+    /// they fold everything to nothing.
+    pub fn adjust(&mut self, steps: i32) {
+        match self {
+            Self::Mandelbulb { power } => *power = (*power + f64::from(steps)).max(2.0),
+            Self::Mandelbox { scale } => {
+                let adjusted = *scale + 0.25 * f64::from(steps);
+                *scale = if adjusted.abs() < 1.0 {
+                    -*scale
+                } else {
+                    adjusted
+                };
+            }
+            Self::QuaternionJulia { slice, .. } => *slice += 0.05 * f64::from(steps),
+        }
+    }
+
+    /// Camera looking at the whole shape.
+    pub fn home(self) -> Camera {
+        let distance = match self {
+            Self::Mandelbulb { .. } | Self::QuaternionJulia { .. } => 3.0,
+            Self::Mandelbox { .. } => 8.0,
+        };
+        Camera {
+            position: Vector::new(0.0, 0.0, -distance),
+            ..Default::default()
+        }
+    }
+
+    /// Shape number, parameter and quaternion constant as `raymarch.cl` expects them.
+    fn kernel_arguments(self) -> (u32, f64, [f64; 4]) {
+        match self {
+            Self::Mandelbulb { power } => (0, power, [0.0; 4]),
+            Self::Mandelbox { scale } => (1, scale, [0.0; 4]),
+            Self::QuaternionJulia { constant, slice } => (2, slice, constant.kernel_argument()),
+        }
+    }
+
+    /// Lower bound on the distance from `point` to the surface, identical to `distance_estimate`
+    /// in `raymarch.cl`.
+    pub fn distance_estimate(self, point: Vector) -> f64 {
+        match self {
+            Self::Mandelbulb { power } => {
+                let mut z = point;
+                let mut derivative = 1.0;
+                let mut radius = 0.0;
+                for _ in 0..MANDELBULB_ITERATIONS {
+                    radius = z.length();
+                    if radius > MANDELBULB_BAILOUT {
+                        break;
+                    }
+                    let theta = (z.z / radius).acos() * power;
+                    let phi = z.y.atan2(z.x) * power;
+                    derivative = radius.powf(power - 1.0) * power * derivative + 1.0;
+                    z = Vector::new(
+                        theta.sin() * phi.cos(),
+                        phi.sin() * theta.sin(),
+                        theta.cos(),
+                    ) * radius.powf(power)
+                        + point;
+                }
+                0.5 * radius.ln() * radius / derivative
+            }
+            Self::Mandelbox { scale } => {
+                let mut z = point;
+                let mut derivative = 1.0;
+                for _ in 0..MANDELBOX_ITERATIONS {
+                    // Box fold
+                    z = z.map(|component| component.clamp(-1.0, 1.0) * 2.0 - component);
+                    // Sphere fold
+                    let radius_squared = z.dot(z);
+                    let factor = if radius_squared < MANDELBOX_MIN_RADIUS_SQUARED {
+                        MANDELBOX_FIXED_RADIUS_SQUARED / MANDELBOX_MIN_RADIUS_SQUARED
+                    } else if radius_squared < MANDELBOX_FIXED_RADIUS_SQUARED {
+                        MANDELBOX_FIXED_RADIUS_SQUARED / radius_squared
+                    } else {
+                        1.0
+                    };
+                    z = z * (factor * scale) + point;
+                    derivative = derivative * factor * scale.abs() + 1.0;
+                }
+                z.length() / derivative.abs()
+            }
+            Self::QuaternionJulia { constant, slice } => {
+                let mut q = Quaternion::from_point(point, slice);
+                // Quaternion norms multiply, so the norm of dq/dq₀ is enough
+                let mut derivative = 1.0;
+                for _ in 0..QUATERNION_JULIA_ITERATIONS {
+                    if q.norm_sqr() > QUATERNION_JULIA_BAILOUT * QUATERNION_JULIA_BAILOUT {
+                        break;
+                    }
+                    derivative *= 2.0 * q.norm();
+                    q = q.square() + constant;
+                }
+                let radius = q.norm();
+                0.5 * radius * radius.ln() / derivative
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mandelbulb { power } => write!(f, "Mandelbulb (power {power})"),
+            Self::Mandelbox { scale } => write!(f, "Mandelbox (scale {scale})"),
+            Self::QuaternionJulia { constant, slice } => {
+                write!(f, "Quaternion Julia set (c = {constant}, slice {slice:.2})")
+            }
+        }
+    }
+}
+
+/// Pinhole camera, turned by `yaw` around the vertical axis and then by `pitch` up or down.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Camera {
+    pub position: Vector,
+    pub yaw: f64,
+    pub pitch: f64,
+    /// Horizontal field of view in radians.
+    pub field_of_view: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: Vector::default(),
+            yaw: 0.0,
+            pitch: 0.0,
+            field_of_view: FRAC_PI_2,
+        }
+    }
+}
+
+impl Camera {
+    pub fn forward(&self) -> Vector {
+        Vector::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        )
+    }
+
+    pub fn right(&self) -> Vector {
+        Vector::new(0.0, 1.0, 0.0)
+            .cross(self.forward())
+            .normalised()
+    }
+
+    pub fn up(&self) -> Vector {
+        self.forward().cross(self.right())
+    }
+
+    /// Moves the camera along its own axes.
+    pub fn fly(&mut self, forward: f64, right: f64, up: f64) {
+        self.position =
+            self.position + self.forward() * forward + self.right() * right + self.up() * up;
+    }
+
+    /// Turns the camera, keeping it from looking straight up or down where `right` breaks down.
+    pub fn turn(&mut self, yaw: f64, pitch: f64) {
+        const MAX_PITCH: f64 = FRAC_PI_2 * 0.99;
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Tangent of half the horizontal field of view, which scales pixel offsets into directions.
+    fn tan_half_field_of_view(&self) -> f64 {
+        (self.field_of_view / 2.0).tan()
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct RayMarchParameters {
+    pub shape: Shape,
+    pub camera: Camera,
+    pub soft_shadows: bool,
+    pub ambient_occlusion: bool,
+}
+
+impl RayMarchParameters {
+    /// Direction of the ray through the center of `pixel`, identical to `ray_direction` in
+    /// `raymarch.cl`.
+    fn ray_direction(&self, pixel: usize, width: usize, height: usize) -> Vector {
+        let scale = self.camera.tan_half_field_of_view();
+        let (x, y) = ((pixel % width) as f64, (pixel / width) as f64);
+        let u = (2.0 * (x + 0.5) / width as f64 - 1.0) * scale;
+        let v = (1.0 - 2.0 * (y + 0.5) / height as f64) * scale * height as f64 / width as f64;
+        (self.camera.forward() + self.camera.right() * u + self.camera.up() * v).normalised()
+    }
+
+    fn normal(&self, point: Vector) -> Vector {
+        let estimate = |offset: Vector| self.shape.distance_estimate(point + offset);
+        let (x, y, z) = (
+            Vector::new(NORMAL_OFFSET, 0.0, 0.0),
+            Vector::new(0.0, NORMAL_OFFSET, 0.0),
+            Vector::new(0.0, 0.0, NORMAL_OFFSET),
+        );
+        Vector::new(
+            estimate(x) - estimate(-x),
+            estimate(y) - estimate(-y),
+            estimate(z) - estimate(-z),
+        )
+        .normalised()
+    }
+
+    /// How much light reaches `point`, from 0 in full shadow to 1, softened by how narrowly the
+    /// shadow ray misses the surface.
+    fn soft_shadow(&self, point: Vector, precision: f64) -> f64 {
+        let mut light = 1.0_f64;
+        let mut distance = precision * 10.0;
+        for _ in 0..SHADOW_STEPS {
+            let estimate = self
+                .shape
+                .distance_estimate(point + LIGHT_DIRECTION * distance);
+            if estimate < precision {
+                return 0.0;
+            }
+            light = light.min(SHADOW_SHARPNESS * estimate / distance);
+            distance += estimate;
+            if distance > MAX_DISTANCE {
+                break;
+            }
+        }
+        light
+    }
+
+    /// Fraction of ambient light reaching `point`, lower in creases the surface closes around.
+    fn ambient_occlusion(&self, point: Vector, normal: Vector) -> f64 {
+        let mut occlusion = 0.0;
+        let mut weight = 1.0;
+        for sample in 1..=AMBIENT_OCCLUSION_SAMPLES {
+            let distance = AMBIENT_OCCLUSION_SPACING * f64::from(sample);
+            let estimate = self.shape.distance_estimate(point + normal * distance);
+            occlusion += weight * (distance - estimate);
+            weight *= 0.5;
+        }
+        (1.0 - occlusion / AMBIENT_OCCLUSION_SPACING / 2.0).clamp(0.0, 1.0)
+    }
+
+    /// Marches the ray through `pixel` and shades what it hits, identical to `shade` in
+    /// `raymarch.cl`.
+    fn shade(&self, pixel: usize, width: usize, height: usize) -> [u8; 3] {
+        let direction = self.ray_direction(pixel, width, height);
+        // Size of a pixel one unit away from the camera
+        let pixel_size = 2.0 * self.camera.tan_half_field_of_view() / width as f64;
+        let mut distance = 0.0;
+        let mut colour = None;
+        for _ in 0..MAX_STEPS {
+            let point = self.camera.position + direction * distance;
+            let estimate = self.shape.distance_estimate(point);
+            let precision = HIT_PRECISION * pixel_size * distance.max(1e-3);
+            if estimate < precision {
+                let normal = self.normal(point);
+                let diffuse = normal.dot(LIGHT_DIRECTION).max(0.0);
+                let shadow = if self.soft_shadows && diffuse > 0.0 {
+                    self.soft_shadow(point + normal * precision, precision)
+                } else {
+                    1.0
+                };
+                let occlusion = if self.ambient_occlusion {
+                    self.ambient_occlusion(point, normal)
+                } else {
+                    1.0
+                };
+                let brightness =
+                    AMBIENT_LIGHT * occlusion + (1.0 - AMBIENT_LIGHT) * diffuse * shadow;
+                colour = Some(SURFACE_COLOUR * brightness);
+                break;
+            }
+            distance += estimate;
+            if distance > MAX_DISTANCE {
+                break;
+            }
+        }
+        let colour = colour.unwrap_or_else(|| {
+            let t = direction.y.max(0.0);
+            HORIZON_COLOUR * (1.0 - t) + SKY_COLOUR * t
+        });
+        // Gamma corrected
+        [colour.x, colour.y, colour.z]
+            .map(|channel| (channel.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8)
+    }
+}
+
+pub trait RayMarchRenderer {
+    type Error;
+
+    fn render(&mut self, parameters: &RayMarchParameters) -> Result<RgbImage, Self::Error>;
+}
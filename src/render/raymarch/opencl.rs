@@ -0,0 +1,107 @@
+use std::{num::NonZeroUsize, ptr};
+
+use opencl3::{
+    command_queue::CommandQueue,
+    context::Context,
+    error_codes::ClError,
+    kernel::{ExecuteKernel, Kernel},
+    memory::Buffer,
+    program::Program,
+    types::CL_NON_BLOCKING,
+};
+
+use crate::{error::OpenclError, rgb_image::RgbImage, Dimensions};
+
+use super::{super::opencl::default_device, RayMarchParameters, RayMarchRenderer};
+
+static KERNEL_SOURCE: &str = include_str!("raymarch.cl");
+
+pub struct OpenclRayMarchRenderer {
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+    context: Context,
+    queue: CommandQueue,
+    kernel: Kernel,
+    buffer: Buffer<u8>,
+}
+
+fn create_output_buffer(
+    context: &Context,
+    width: NonZeroUsize,
+    height: NonZeroUsize,
+) -> Result<Buffer<u8>, OpenclError> {
+    let byte_count = width
+        .checked_mul(height)
+        .and_then(|pixel_count| pixel_count.checked_mul(NonZeroUsize::new(3).unwrap()))
+        .ok_or(OpenclError::IntegerOverflow)?;
+    Ok(unsafe { Buffer::create(context, 0, byte_count.get(), ptr::null_mut()) }?)
+}
+
+impl OpenclRayMarchRenderer {
+    pub fn new(width: NonZeroUsize, height: NonZeroUsize) -> Result<Self, OpenclError> {
+        let device = default_device()?;
+        let context = Context::from_device(&device)?;
+        let queue = CommandQueue::create_default_with_properties(&context, 0, 0)?;
+        let program = Program::create_and_build_from_source(&context, KERNEL_SOURCE, "")?;
+        let kernel = Kernel::create(&program, "ray_march")?;
+        let buffer = create_output_buffer(&context, width, height)?;
+        Ok(Self {
+            width,
+            height,
+            context,
+            queue,
+            kernel,
+            buffer,
+        })
+    }
+
+    pub fn resize(&mut self, Dimensions { width, height }: Dimensions) -> Result<(), OpenclError> {
+        self.buffer = create_output_buffer(&self.context, width, height)?;
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+}
+
+impl RayMarchRenderer for OpenclRayMarchRenderer {
+    type Error = ClError;
+
+    fn render(&mut self, parameters: &RayMarchParameters) -> Result<RgbImage, Self::Error> {
+        let pixel_count = self.width.get() * self.height.get();
+        let camera = &parameters.camera;
+        let (shape, shape_parameter, constant) = parameters.shape.kernel_arguments();
+
+        let kernel_event = unsafe {
+            ExecuteKernel::new(&self.kernel)
+                .set_arg(&(self.width.get() as u64))
+                .set_arg(&(self.height.get() as u64))
+                .set_arg(&camera.position.kernel_argument())
+                .set_arg(&camera.forward().kernel_argument())
+                .set_arg(&camera.right().kernel_argument())
+                .set_arg(&camera.up().kernel_argument())
+                .set_arg(&camera.tan_half_field_of_view())
+                .set_arg(&shape)
+                .set_arg(&shape_parameter)
+                .set_arg(&constant)
+                .set_arg(&u32::from(parameters.soft_shadows))
+                .set_arg(&u32::from(parameters.ambient_occlusion))
+                .set_arg(&self.buffer)
+                .set_global_work_size(pixel_count)
+                .enqueue_nd_range(&self.queue)
+        }?;
+
+        let mut buffer = vec![0; pixel_count * 3];
+        let read_buffer_event = unsafe {
+            self.queue.enqueue_read_buffer(
+                &self.buffer,
+                CL_NON_BLOCKING,
+                0,
+                &mut buffer,
+                &[kernel_event.get()],
+            )
+        }?;
+        read_buffer_event.wait()?;
+        // Buffer size was already calculated
+        Ok(RgbImage::from_buffer(buffer, self.width).unwrap())
+    }
+}
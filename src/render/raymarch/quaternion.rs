@@ -0,0 +1,77 @@
+use std::ops::Add;
+
+use num::Complex;
+
+use super::Vector;
+
+/// Hypercomplex number w + xi + yj + zk.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub const fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    /// Embeds a point in space as the first three components, with `slice` as the fourth.
+    pub fn from_point(point: Vector, slice: f64) -> Self {
+        Self::new(point.x, point.y, point.z, slice)
+    }
+
+    pub fn norm_sqr(self) -> f64 {
+        self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z
+    }
+
+    pub fn norm(self) -> f64 {
+        self.norm_sqr().sqrt()
+    }
+
+    /// q², where the imaginary parts only ever get scaled since they commute with themselves.
+    pub fn square(self) -> Self {
+        Self::new(
+            self.w * self.w - self.x * self.x - self.y * self.y - self.z * self.z,
+            2.0 * self.w * self.x,
+            2.0 * self.w * self.y,
+            2.0 * self.w * self.z,
+        )
+    }
+
+    /// Passed to OpenCL as a `double4`.
+    pub fn kernel_argument(self) -> [f64; 4] {
+        [self.w, self.x, self.y, self.z]
+    }
+}
+
+impl From<Complex<f64>> for Quaternion {
+    fn from(value: Complex<f64>) -> Self {
+        Self::new(value.re, value.im, 0.0, 0.0)
+    }
+}
+
+impl Add for Quaternion {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(
+            self.w + other.w,
+            self.x + other.x,
+            self.y + other.y,
+            self.z + other.z,
+        )
+    }
+}
+
+impl std::fmt::Display for Quaternion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.3}{:+.3}i{:+.3}j{:+.3}k",
+            self.w, self.x, self.y, self.z
+        )
+    }
+}
@@ -0,0 +1,166 @@
+// Constants as in `raymarch/mod.rs` on the host
+#define MANDELBULB_ITERATIONS 12
+#define MANDELBULB_BAILOUT 2.0
+#define MANDELBOX_ITERATIONS 16
+#define MANDELBOX_MIN_RADIUS_SQUARED 0.25
+#define MANDELBOX_FIXED_RADIUS_SQUARED 1.0
+#define QUATERNION_JULIA_ITERATIONS 16
+#define QUATERNION_JULIA_BAILOUT 4.0
+
+#define MAX_STEPS 256
+#define MAX_DISTANCE 50.0
+#define HIT_PRECISION 0.5
+#define NORMAL_OFFSET 1e-5
+#define SHADOW_SHARPNESS 8.0
+#define SHADOW_STEPS 64
+#define AMBIENT_OCCLUSION_SAMPLES 5
+#define AMBIENT_OCCLUSION_SPACING 0.02
+#define AMBIENT_LIGHT 0.2
+
+#define LIGHT_DIRECTION ((double3) (0.577, 0.577, -0.577))
+#define SURFACE_COLOUR ((double3) (0.9, 0.75, 0.55))
+#define SKY_COLOUR ((double3) (0.35, 0.5, 0.75))
+#define HORIZON_COLOUR ((double3) (0.05, 0.05, 0.1))
+
+// q², like `Quaternion::square` on the host, with the components in w, x, y, z order
+double4 quaternion_square(double4 q) {
+    return (double4) (q.x * q.x - dot(q.yzw, q.yzw), 2.0 * q.x * q.yzw);
+}
+
+// Must match `Shape::distance_estimate` on the host, with shapes numbered as in
+// `Shape::kernel_arguments`
+double distance_estimate(uint shape, double parameter, double4 constant, double3 point) {
+    double3 z = point;
+    double derivative = 1.0;
+    if (shape == 0) {
+        const double power = parameter;
+        double radius = 0.0;
+        for (uint iteration = 0; iteration < MANDELBULB_ITERATIONS; iteration++) {
+            radius = length(z);
+            if (radius > MANDELBULB_BAILOUT) {
+                break;
+            }
+            const double theta = acos(z.z / radius) * power;
+            const double phi = atan2(z.y, z.x) * power;
+            derivative = pow(radius, power - 1.0) * power * derivative + 1.0;
+            z = (double3) (sin(theta) * cos(phi), sin(phi) * sin(theta), cos(theta)) * pow(radius, power) + point;
+        }
+        return 0.5 * log(radius) * radius / derivative;
+    }
+    if (shape == 2) {
+        double4 q = (double4) (point, parameter);
+        for (uint iteration = 0; iteration < QUATERNION_JULIA_ITERATIONS; iteration++) {
+            if (dot(q, q) > QUATERNION_JULIA_BAILOUT * QUATERNION_JULIA_BAILOUT) {
+                break;
+            }
+            derivative *= 2.0 * length(q);
+            q = quaternion_square(q) + constant;
+        }
+        const double radius = length(q);
+        return 0.5 * radius * log(radius) / derivative;
+    }
+    const double scale = parameter;
+    for (uint iteration = 0; iteration < MANDELBOX_ITERATIONS; iteration++) {
+        z = clamp(z, -1.0, 1.0) * 2.0 - z;
+        const double radius_squared = dot(z, z);
+        double factor = 1.0;
+        if (radius_squared < MANDELBOX_MIN_RADIUS_SQUARED) {
+            factor = MANDELBOX_FIXED_RADIUS_SQUARED / MANDELBOX_MIN_RADIUS_SQUARED;
+        } else if (radius_squared < MANDELBOX_FIXED_RADIUS_SQUARED) {
+            factor = MANDELBOX_FIXED_RADIUS_SQUARED / radius_squared;
+        }
+        z = z * (factor * scale) + point;
+        derivative = derivative * factor * fabs(scale) + 1.0;
+    }
+    return length(z) / fabs(derivative);
+}
+
+// Must match `RayMarchParameters::ray_direction` on the host
+double3 ray_direction(ulong pixel, ulong width, ulong height, double3 forward, double3 right, double3 up, double tan_half_field_of_view) {
+    const double x = (double) (pixel % width);
+    const double y = (double) (pixel / width);
+    const double u = (2.0 * (x + 0.5) / (double) width - 1.0) * tan_half_field_of_view;
+    const double v = (1.0 - 2.0 * (y + 0.5) / (double) height) * tan_half_field_of_view * (double) height / (double) width;
+    return normalize(forward + right * u + up * v);
+}
+
+double3 surface_normal(uint shape, double parameter, double4 constant, double3 point) {
+    const double3 x = (double3) (NORMAL_OFFSET, 0.0, 0.0);
+    const double3 y = (double3) (0.0, NORMAL_OFFSET, 0.0);
+    const double3 z = (double3) (0.0, 0.0, NORMAL_OFFSET);
+    return normalize((double3) (
+        distance_estimate(shape, parameter, constant, point + x) - distance_estimate(shape, parameter, constant, point - x),
+        distance_estimate(shape, parameter, constant, point + y) - distance_estimate(shape, parameter, constant, point - y),
+        distance_estimate(shape, parameter, constant, point + z) - distance_estimate(shape, parameter, constant, point - z)
+    ));
+}
+
+// Must match `RayMarchParameters::soft_shadow` on the host
+double soft_shadow(uint shape, double parameter, double4 constant, double3 point, double precision) {
+    double light = 1.0;
+    double distance = precision * 10.0;
+    for (uint step = 0; step < SHADOW_STEPS; step++) {
+        const double estimate = distance_estimate(shape, parameter, constant, point + LIGHT_DIRECTION * distance);
+        if (estimate < precision) {
+            return 0.0;
+        }
+        light = fmin(light, SHADOW_SHARPNESS * estimate / distance);
+        distance += estimate;
+        if (distance > MAX_DISTANCE) {
+            break;
+        }
+    }
+    return light;
+}
+
+// Must match `RayMarchParameters::ambient_occlusion` on the host
+double ambient_occlusion(uint shape, double parameter, double4 constant, double3 point, double3 normal) {
+    double occlusion = 0.0;
+    double weight = 1.0;
+    for (uint sample = 1; sample <= AMBIENT_OCCLUSION_SAMPLES; sample++) {
+        const double distance = AMBIENT_OCCLUSION_SPACING * (double) sample;
+        const double estimate = distance_estimate(shape, parameter, constant, point + normal * distance);
+        occlusion += weight * (distance - estimate);
+        weight *= 0.5;
+    }
+    return clamp(1.0 - occlusion / AMBIENT_OCCLUSION_SPACING / 2.0, 0.0, 1.0);
+}
+
+// Must match `RayMarchParameters::shade` on the host, with vectors passed as `double4` for
+// predictable alignment
+kernel void ray_march(ulong width, ulong height, double4 position, double4 forward, double4 right, double4 up, double tan_half_field_of_view, uint shape, double parameter, double4 constant, uint soft_shadows, uint ambient_occlusion_enabled, global uchar* output) {
+    const size_t pixel = get_global_id(0);
+    const double3 direction = ray_direction(pixel, width, height, forward.xyz, right.xyz, up.xyz, tan_half_field_of_view);
+    const double pixel_size = 2.0 * tan_half_field_of_view / (double) width;
+
+    double distance = 0.0;
+    bool hit = false;
+    double3 colour;
+    for (uint step = 0; step < MAX_STEPS; step++) {
+        const double3 point = position.xyz + direction * distance;
+        const double estimate = distance_estimate(shape, parameter, constant, point);
+        const double precision = HIT_PRECISION * pixel_size * fmax(distance, 1e-3);
+        if (estimate < precision) {
+            const double3 normal = surface_normal(shape, parameter, constant, point);
+            const double diffuse = fmax(dot(normal, LIGHT_DIRECTION), 0.0);
+            const double shadow = soft_shadows && diffuse > 0.0 ? soft_shadow(shape, parameter, constant, point + normal * precision, precision) : 1.0;
+            const double occlusion = ambient_occlusion_enabled ? ambient_occlusion(shape, parameter, constant, point, normal) : 1.0;
+            const double brightness = AMBIENT_LIGHT * occlusion + (1.0 - AMBIENT_LIGHT) * diffuse * shadow;
+            colour = SURFACE_COLOUR * brightness;
+            hit = true;
+            break;
+        }
+        distance += estimate;
+        if (distance > MAX_DISTANCE) {
+            break;
+        }
+    }
+    if (!hit) {
+        const double t = fmax(direction.y, 0.0);
+        colour = HORIZON_COLOUR * (1.0 - t) + SKY_COLOUR * t;
+    }
+    const double3 corrected = pow(clamp(colour, 0.0, 1.0), (double3) (1.0 / 2.2)) * 255.0;
+    output[pixel * 3] = (uchar) round(corrected.x);
+    output[pixel * 3 + 1] = (uchar) round(corrected.y);
+    output[pixel * 3 + 2] = (uchar) round(corrected.z);
+}
@@ -0,0 +1,174 @@
+use std::num::NonZeroUsize;
+
+/// Upper bound on samples taken for a single pixel, used to check buffer sizes up front.
+pub const MAX_SAMPLES_PER_PIXEL: usize = 9;
+
+/// Divisor of `max_iterations` giving the difference two neighbouring pixels must reach to be
+/// supersampled in adaptive mode, so the threshold is 1/32 of the limit.
+const ADAPTIVE_THRESHOLD_DIVISOR: u32 = 32;
+
+/// Escaping pixels closer to the set than this many pixels are always supersampled when a
+/// distance estimate is available.
+const ADAPTIVE_DISTANCE: f32 = 1.0;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SamplePattern {
+    Grid2x2,
+    Grid3x3,
+    RotatedGrid,
+    Jittered,
+}
+
+impl SamplePattern {
+    /// Sample positions relative to the pixel, in pixels, interleaved as `[x0, y0, x1, y1, ...]`.
+    fn offsets(self) -> &'static [f64] {
+        match self {
+            Self::Grid2x2 | Self::Jittered => &[-0.25, -0.25, 0.25, -0.25, -0.25, 0.25, 0.25, 0.25],
+            Self::Grid3x3 => &[
+                -1.0 / 3.0,
+                -1.0 / 3.0,
+                0.0,
+                -1.0 / 3.0,
+                1.0 / 3.0,
+                -1.0 / 3.0,
+                -1.0 / 3.0,
+                0.0,
+                0.0,
+                0.0,
+                1.0 / 3.0,
+                0.0,
+                -1.0 / 3.0,
+                1.0 / 3.0,
+                0.0,
+                1.0 / 3.0,
+                1.0 / 3.0,
+                1.0 / 3.0,
+            ],
+            Self::RotatedGrid => &[-0.125, -0.375, 0.375, -0.125, 0.125, 0.375, -0.375, 0.125],
+        }
+    }
+
+    /// Size of the square each sample is randomly displaced within.
+    fn jitter(self) -> f64 {
+        match self {
+            Self::Jittered => 0.5,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Supersampling {
+    pub pattern: Option<SamplePattern>,
+    pub adaptive: bool,
+}
+
+impl Supersampling {
+    /// Cycles through off and every sample pattern.
+    pub fn next_pattern(&mut self) {
+        self.pattern = match self.pattern {
+            None => Some(SamplePattern::Grid2x2),
+            Some(SamplePattern::Grid2x2) => Some(SamplePattern::Grid3x3),
+            Some(SamplePattern::Grid3x3) => Some(SamplePattern::RotatedGrid),
+            Some(SamplePattern::RotatedGrid) => Some(SamplePattern::Jittered),
+            Some(SamplePattern::Jittered) => None,
+        };
+    }
+
+    pub fn offsets(&self) -> &'static [f64] {
+        match self.pattern {
+            Some(pattern) => pattern.offsets(),
+            None => &[0.0, 0.0],
+        }
+    }
+
+    pub fn jitter(&self) -> f64 {
+        self.pattern.map_or(0.0, SamplePattern::jitter)
+    }
+
+    pub fn samples_per_pixel(&self) -> NonZeroUsize {
+        // Every offset table has at least one sample
+        NonZeroUsize::new(self.offsets().len() / 2).unwrap()
+    }
+
+    /// Whether a cheap single-sample pass should decide which pixels get supersampled.
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive && self.pattern.is_some()
+    }
+
+    /// Position of a sample relative to its pixel, identical to `sample_offset` in `kernel.cl`.
+    pub fn sample_offset(&self, pixel: usize, sample: usize) -> (f64, f64) {
+        let offsets = self.offsets();
+        let jitter = self.jitter();
+        let seed = (pixel as u32)
+            .wrapping_mul(offsets.len() as u32)
+            .wrapping_add(sample as u32 * 2);
+        (
+            offsets[sample * 2] + (unit_random(seed) - 0.5) * jitter,
+            offsets[sample * 2 + 1] + (unit_random(seed.wrapping_add(1)) - 0.5) * jitter,
+        )
+    }
+}
+
+impl std::fmt::Display for Supersampling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.pattern {
+            None => write!(f, "Off"),
+            Some(pattern) => {
+                match pattern {
+                    SamplePattern::Grid2x2 => write!(f, "2x2")?,
+                    SamplePattern::Grid3x3 => write!(f, "3x3")?,
+                    SamplePattern::RotatedGrid => write!(f, "Rotated grid")?,
+                    SamplePattern::Jittered => write!(f, "Jittered")?,
+                }
+                if self.adaptive {
+                    write!(f, " (adaptive)")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn hash(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+    x
+}
+
+fn unit_random(seed: u32) -> f64 {
+    f64::from(hash(seed)) / 4294967296.0
+}
+
+/// Marks the pixels of a single-sample image that differ significantly from a 4-neighbour or,
+/// given distance estimates, lie right next to the set.
+pub fn edge_pixels(
+    buffer: &[u32],
+    distances: Option<&[f32]>,
+    width: NonZeroUsize,
+    max_iterations: u32,
+) -> Vec<bool> {
+    let width = width.get();
+    let height = buffer.len() / width;
+    let threshold = (max_iterations / ADAPTIVE_THRESHOLD_DIVISOR).max(1);
+    let differs = |a: u32, b: u32| {
+        (a == max_iterations) != (b == max_iterations) || a.abs_diff(b) >= threshold
+    };
+    (0..buffer.len())
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let value = buffer[i];
+            let near_set = distances.is_some_and(|distances| {
+                value < max_iterations && distances[i] < ADAPTIVE_DISTANCE
+            });
+            near_set
+                || (x > 0 && differs(value, buffer[i - 1]))
+                || (x + 1 < width && differs(value, buffer[i + 1]))
+                || (y > 0 && differs(value, buffer[i - width]))
+                || (y + 1 < height && differs(value, buffer[i + width]))
+        })
+        .collect()
+}
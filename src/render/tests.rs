@@ -0,0 +1,286 @@
+//! Golden image regression tests. Every reference view is rendered at a small size and compared
+//! with the iteration counts checked in under `golden/`. Run the tests with `UPDATE_GOLDEN` set to
+//! write the current CPU renders there instead, after a change that is meant to alter them.
+
+use std::{env, fs, num::NonZeroUsize, path::PathBuf};
+
+use num::Complex;
+
+use crate::{
+    comparison::Difference, formula::Formula, iteration_image::IterationImage, Dimensions,
+};
+
+use super::{
+    cpu::ScalarCpuRenderer,
+    opencl::{default_device, OpenclRenderer},
+    supersampling::{SamplePattern, Supersampling},
+    transform::ViewTransform,
+    RenderOptions, Renderer,
+};
+
+const WIDTH: usize = 48;
+const HEIGHT: usize = 32;
+
+/// Fraction of pixels the OpenCL renders may differ from the golden images in, since pixels right
+/// on the boundary of the set can escape or not depending on rounding.
+const MAX_MISMATCHED_FRACTION: f64 = 0.01;
+
+struct ReferenceView {
+    name: &'static str,
+    center: Complex<f64>,
+    radius: f64,
+    max_iterations: u32,
+    options: RenderOptions,
+    formula: Option<&'static str>,
+}
+
+fn reference_views() -> [ReferenceView; 7] {
+    [
+        ReferenceView {
+            name: "mandelbrot",
+            center: Complex::new(-0.75, 0.0),
+            radius: 2.0,
+            max_iterations: 256,
+            options: RenderOptions::default(),
+            formula: None,
+        },
+        ReferenceView {
+            name: "seahorse_valley",
+            center: Complex::new(-0.745, 0.1),
+            radius: 0.05,
+            max_iterations: 1000,
+            options: RenderOptions::default(),
+            formula: None,
+        },
+        ReferenceView {
+            name: "julia",
+            center: Complex::new(0.0, 0.0),
+            radius: 1.8,
+            max_iterations: 256,
+            options: RenderOptions {
+                julia: Some(Complex::new(-0.8, 0.156)),
+                ..RenderOptions::default()
+            },
+            formula: None,
+        },
+        ReferenceView {
+            name: "supersampled",
+            center: Complex::new(-0.75, 0.0),
+            radius: 2.0,
+            max_iterations: 256,
+            options: RenderOptions {
+                supersampling: Supersampling {
+                    pattern: Some(SamplePattern::Grid2x2),
+                    adaptive: false,
+                },
+                ..RenderOptions::default()
+            },
+            formula: None,
+        },
+        ReferenceView {
+            name: "cubic_formula",
+            center: Complex::new(0.0, 0.0),
+            radius: 1.5,
+            max_iterations: 128,
+            options: RenderOptions::default(),
+            formula: Some("z^3 + c"),
+        },
+        // Formulas have no derivative, so asking for one has to leave the image alone
+        ReferenceView {
+            name: "formula_distance_estimate",
+            center: Complex::new(0.0, 0.0),
+            radius: 1.5,
+            max_iterations: 128,
+            options: RenderOptions {
+                distance_estimation: true,
+                normals: true,
+                ..RenderOptions::default()
+            },
+            formula: Some("z^3 + c"),
+        },
+        ReferenceView {
+            name: "transformed",
+            center: Complex::new(-0.75, 0.0),
+            radius: 1.5,
+            max_iterations: 256,
+            options: RenderOptions {
+                distance_estimation: true,
+                transform: ViewTransform::new(0.6, 1.5, 0.4),
+                ..RenderOptions::default()
+            },
+            formula: None,
+        },
+    ]
+}
+
+fn dimensions() -> Dimensions {
+    Dimensions {
+        width: NonZeroUsize::new(WIDTH).unwrap(),
+        height: NonZeroUsize::new(HEIGHT).unwrap(),
+    }
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "src", "render", "golden"]
+        .iter()
+        .collect::<PathBuf>()
+        .join(format!("{name}.txt"))
+}
+
+/// A header line with the width, samples per pixel and iteration limit, then one line of
+/// iteration counts per row.
+fn write_golden(name: &str, image: &IterationImage) {
+    let row_length = image.width().get() * image.samples_per_pixel().get();
+    let mut dump = format!(
+        "{} {} {}\n",
+        image.width(),
+        image.samples_per_pixel(),
+        image.max_iterations()
+    );
+    for row in image.iterations().chunks(row_length) {
+        let row = row.iter().map(u32::to_string).collect::<Vec<_>>().join(" ");
+        dump.push_str(&row);
+        dump.push('\n');
+    }
+    fs::write(golden_path(name), dump).unwrap();
+}
+
+fn read_golden(name: &str) -> IterationImage {
+    let path = golden_path(name);
+    let dump = fs::read_to_string(&path).unwrap_or_else(|error| {
+        panic!(
+            "cannot read {}: {error}; run the tests with UPDATE_GOLDEN=1 to create it",
+            path.display()
+        )
+    });
+    let mut numbers = dump
+        .split_whitespace()
+        .map(|number| number.parse::<u32>().unwrap());
+    let mut header = || numbers.next().unwrap() as usize;
+    let width = NonZeroUsize::new(header()).unwrap();
+    let samples_per_pixel = NonZeroUsize::new(header()).unwrap();
+    let max_iterations = header() as u32;
+    IterationImage::from_buffer(numbers.collect(), width, samples_per_pixel, max_iterations)
+        .unwrap()
+}
+
+/// Checks that a render of a formula came without the distances and normals only z² + c has.
+fn assert_no_derivative(view: &ReferenceView, image: &IterationImage) {
+    if view.formula.is_none() {
+        return;
+    }
+    for index in 0..image.iterations().len() {
+        let sample = image.sample(index);
+        assert!(
+            sample.distance.is_none() && sample.normal.is_none(),
+            "{}: sample {index} has a distance or normal",
+            view.name
+        );
+    }
+}
+
+fn render_cpu(view: &ReferenceView) -> IterationImage {
+    let Dimensions { width, height } = dimensions();
+    let mut renderer = ScalarCpuRenderer::new(width, height).unwrap();
+    renderer.set_formula(
+        view.formula
+            .map(|source| Formula::parse(source).unwrap())
+            .as_ref(),
+    );
+    let Ok(image) = Renderer::<f64>::render(
+        &mut renderer,
+        view.center,
+        view.radius,
+        view.max_iterations,
+        &view.options,
+    );
+    image
+}
+
+#[test]
+fn cpu_matches_golden_images() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    for view in reference_views() {
+        let image = render_cpu(&view);
+        assert_no_derivative(&view, &image);
+        if update {
+            write_golden(view.name, &image);
+            continue;
+        }
+        let golden = read_golden(view.name);
+        let difference = Difference::new(&image, &golden);
+        assert_eq!(
+            difference.mismatched_pixels(),
+            0,
+            "{}: {difference}",
+            view.name
+        );
+    }
+}
+
+/// Distances are in pixels of the transformed plane, so scaling the view up twofold has to give the
+/// same distances as zooming out twofold.
+#[test]
+fn distances_follow_the_transform() {
+    let view = |radius, transform| ReferenceView {
+        name: "scaled",
+        center: Complex::new(-0.75, 0.0),
+        radius,
+        max_iterations: 256,
+        options: RenderOptions {
+            distance_estimation: true,
+            transform,
+            ..RenderOptions::default()
+        },
+        formula: None,
+    };
+    let scaled = render_cpu(&view(
+        1.0,
+        ViewTransform {
+            matrix: [[2.0, 0.0], [0.0, 2.0]],
+        },
+    ));
+    let zoomed_out = render_cpu(&view(2.0, ViewTransform::default()));
+    for index in 0..scaled.iterations().len() {
+        let (Some(scaled), Some(zoomed_out)) = (
+            scaled.sample(index).distance,
+            zoomed_out.sample(index).distance,
+        ) else {
+            continue;
+        };
+        assert!(
+            (scaled - zoomed_out).abs() <= zoomed_out.abs() * 1e-4,
+            "sample {index}: {scaled} instead of {zoomed_out}"
+        );
+    }
+}
+
+#[test]
+fn opencl_agrees_with_golden_images() {
+    if let Err(error) = default_device() {
+        eprintln!("skipping OpenCL golden image test: {error:?}");
+        return;
+    }
+    let Dimensions { width, height } = dimensions();
+    let mut renderer = OpenclRenderer::new(width, height).unwrap();
+    for view in reference_views() {
+        renderer
+            .set_formula(
+                view.formula
+                    .map(|source| Formula::parse(source).unwrap())
+                    .as_ref(),
+            )
+            .unwrap();
+        let image = renderer
+            .render(view.center, view.radius, view.max_iterations, &view.options)
+            .unwrap();
+        assert_no_derivative(&view, &image);
+        let difference = Difference::new(&image, &read_golden(view.name));
+        assert!(
+            difference.mismatched_pixels() as f64
+                <= difference.pixel_count() as f64 * MAX_MISMATCHED_FRACTION,
+            "{}: {difference}",
+            view.name
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use num::{traits::float::FloatCore, Complex};
+
+/// Linear map applied to every point's offset from the center of the view, so views can be
+/// rotated, and stretched or skewed to undo the distortion of minibrots near the boundary of
+/// bigger ones. Stored as the rows of a 2x2 matrix acting on (re, im).
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct ViewTransform {
+    pub matrix: [[f64; 2]; 2],
+}
+
+impl Default for ViewTransform {
+    fn default() -> Self {
+        Self {
+            matrix: [[1.0, 0.0], [0.0, 1.0]],
+        }
+    }
+}
+
+impl ViewTransform {
+    /// Stretches the real axis by `stretch` and shears it by `skew`, then rotates anticlockwise
+    /// by `angle` radians.
+    pub fn new(angle: f64, stretch: f64, skew: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            matrix: [
+                [cos * stretch, cos * skew - sin],
+                [sin * stretch, sin * skew + cos],
+            ],
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, offset: Complex<f64>) -> Complex<f64> {
+        let [[a, b], [c, d]] = self.matrix;
+        Complex::new(a * offset.re + b * offset.im, c * offset.re + d * offset.im)
+    }
+
+    /// The transform undoing this one, or `None` if it flattens the plane.
+    pub fn inverse(&self) -> Option<Self> {
+        let [[a, b], [c, d]] = self.matrix;
+        let determinant = a * d - b * c;
+        (determinant != 0.0 && determinant.is_finite()).then(|| Self {
+            matrix: [
+                [d / determinant, -b / determinant],
+                [-c / determinant, a / determinant],
+            ],
+        })
+    }
+
+    /// Where `point` of the axis-aligned view around `center` ends up. The identity leaves points
+    /// untouched rather than rounding them through their offset. Identical to `transform_point` in
+    /// `common.cl`.
+    pub fn transform_point<T: FloatCore>(
+        &self,
+        point: Complex<T>,
+        center: Complex<T>,
+    ) -> Complex<T> {
+        if self.is_identity() {
+            return point;
+        }
+        let [[a, b], [c, d]] = self
+            .matrix
+            .map(|row| row.map(|entry| T::from(entry).unwrap_or(T::nan())));
+        let offset = point - center;
+        center + Complex::new(a * offset.re + b * offset.im, c * offset.re + d * offset.im)
+    }
+
+    /// How much the transform stretches lengths on average: the square root of how much it
+    /// stretches areas. Identical to `transform_scale` in `common.cl`.
+    pub fn scale(&self) -> f64 {
+        let [[a, b], [c, d]] = self.matrix;
+        (a * d - b * c).abs().sqrt()
+    }
+
+    /// The matrix in the row-major `double4` layout `transform_point` in `common.cl` expects.
+    pub fn kernel_arguments(&self) -> [f64; 4] {
+        let [[a, b], [c, d]] = self.matrix;
+        [a, b, c, d]
+    }
+}
@@ -0,0 +1,30 @@
+use std::num::NonZeroUsize;
+
+use sdl2::render::Texture;
+
+use crate::error::{InvalidBufferSize, WriteToTextureError};
+
+/// An image that was already coloured, three bytes per pixel.
+pub struct RgbImage {
+    buffer: Vec<u8>,
+    width: NonZeroUsize,
+}
+
+impl RgbImage {
+    pub fn from_buffer(buffer: Vec<u8>, width: NonZeroUsize) -> Result<Self, InvalidBufferSize> {
+        if buffer.is_empty() || !buffer.len().is_multiple_of(width.get() * 3) {
+            return Err(InvalidBufferSize);
+        }
+        Ok(Self { buffer, width })
+    }
+
+    pub fn write_to_texture(&self, texture: &mut Texture) -> Result<(), WriteToTextureError> {
+        let query = texture.query();
+        let height = self.buffer.len() / 3 / self.width.get();
+        if self.width.get() != query.width as usize || height != query.height as usize {
+            return Err(WriteToTextureError::DimensionsDoNotMatch);
+        }
+        texture.update(None, &self.buffer, self.width.get() * 3)?;
+        Ok(())
+    }
+}